@@ -0,0 +1,80 @@
+//! Plays two computer players against each other, to compare their strength.
+//!
+//! ```text
+//! cargo run --release --example benchmark -- <player> <player> [games] [budget]
+//! ```
+//!
+//! Players are named as for `--red` and `--blue`, and the budget is the same
+//! as `--budget`, with each player's own default if it isn't given. The players swap colours every game, and each game is drawn
+//! after 2000 moves.
+
+extern crate stratagem;
+
+use std::env;
+use std::time::Instant;
+use stratagem::board::{Board, Colour, GameResult};
+use stratagem::bot::{self, Budget};
+use stratagem::player::play_game;
+
+const USAGE: &str = "Usage: benchmark <player> <player> [games] [budget]";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let names = match args.get(0 .. 2) {
+        Some(names) => names,
+        None => {
+            println!("{}", USAGE);
+            ::std::process::exit(1)
+        }
+    };
+    let games = args.get(2).map_or(Some(10), |s| s.parse().ok());
+    let budget = args.get(3).map(|s| Budget::from(s));
+    let (games, budget) = match (games, budget) {
+        (Some(games), None) => (games, None),
+        (Some(games), Some(Some(budget))) => (games, Some(budget)),
+        _ => {
+            println!("{}", USAGE);
+            ::std::process::exit(1)
+        }
+    };
+
+    // Wins for each player, then draws.
+    let mut tally = [0, 0, 0];
+    let start = Instant::now();
+    for game in 0 .. games {
+        let (first, second) = (game % 2, 1 - game % 2);
+        let player = |n: usize, seed: u64| match bot::named(&names[n], seed, budget) {
+            Ok(Some(player)) => player,
+            Ok(None) => {
+                println!("No such player: {}", names[n]);
+                ::std::process::exit(1)
+            }
+            Err(e) => {
+                println!("{}", e);
+                ::std::process::exit(1)
+            }
+        };
+        let (mut red, mut blue) = (player(first, game as u64 * 2), player(second, game as u64 * 2 + 1));
+
+        let mut board = Board::new();
+        board.set_move_limit(Some(2000));
+        let result = play_game(&mut board, &mut *red, &mut *blue).unwrap_or_else(|e| {
+            println!("Game {} failed: {}", game + 1, e);
+            ::std::process::exit(1)
+        });
+        match result {
+            GameResult::Win(Colour::Red, _) => tally[first] += 1,
+            GameResult::Win(Colour::Blue, _) => tally[second] += 1,
+            _ => tally[2] += 1,
+        }
+        println!(
+            "Game {}: {} as red, {} as blue: {} after {} moves",
+            game + 1, names[first], names[second], result, board.history().len()
+        );
+    }
+
+    println!(
+        "{} {} wins, {} {} wins, {} draws, in {:.1}s",
+        names[0], tally[0], names[1], tally[1], tally[2], start.elapsed().as_secs_f64()
+    );
+}
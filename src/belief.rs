@@ -0,0 +1,264 @@
+//! Estimates of what each hidden enemy piece might be.
+//!
+//! A player knows the army their opponent started with, which of its pieces
+//! have been captured or revealed in battle, which hidden pieces have moved
+//! and so can't be bombs or the flag, and which have moved more than one tile
+//! at once and so must be scouts. The chance of each hidden piece being each
+//! rank is estimated by sharing the ranks still unaccounted for between the
+//! hidden pieces that could be them, balancing the shares until every piece
+//! is exactly one rank and every rank is spread over as many pieces as are
+//! left of it.
+
+use board::{BattleResult, Colour, Coord, Move, Piece, Strike};
+use random::Rng;
+use rules::Rules;
+use view::{Seen, View};
+
+/// Every rank, in the order chances are kept in.
+const RANKS: [Piece; 12] = {
+    use board::Piece::*;
+    [
+        Bomb, Marshall, General, Colonel, Major, Captain, Lieutenant,
+        Sergeant, Miner, Scout, Spy, Flag
+    ]
+};
+
+/// How many times the shares are balanced between pieces and ranks.
+const ROUNDS: usize = 20;
+
+/// One player's estimate of the ranks of their opponent's pieces.
+///
+/// Every move of the game is passed to `observe`, and the chances are worked
+/// out again from the player's view with `update`.
+#[derive(Clone, Debug)]
+pub struct Belief {
+    /// The colour of the player holding the belief.
+    colour: Colour,
+    rules:  Rules,
+    /// The number of moves observed so far.
+    ply:    usize,
+    /// Where the enemy pieces known to be scouts stand.
+    scouts: Vec<Coord>,
+    /// How many of each rank, in the order of `RANKS`, are left hidden.
+    left:   [f64; 12],
+    /// The chance of each rank, in the order of `RANKS`, for the enemy piece
+    /// on each tile. All zero where there is no enemy piece.
+    odds:   Vec<Vec<[f64; 12]>>,
+}
+
+impl Belief {
+    /// A belief for the player of the colour, before any move has been
+    /// played.
+    pub fn new(colour: Colour, rules: Rules) -> Self {
+        let (width, height) = (rules.geometry.width as usize, rules.geometry.height as usize);
+        Belief {
+            colour,
+            rules,
+            ply:    0,
+            scouts: vec![],
+            left:   [0.0; 12],
+            odds:   vec![vec![[0.0; 12]; width]; height],
+        }
+    }
+
+    /// Follows a move by either player, and the battle it caused, if any,
+    /// keeping track of the enemy pieces known to be scouts.
+    pub fn observe(&mut self, mv: Move, strike: Option<Strike>) {
+        let mover = if self.ply.is_multiple_of(2) { Colour::Red } else { Colour::Blue };
+        self.ply += 1;
+        let result = strike.map(|s| s.result);
+        // Whether a defender that won moved onto the attacker's tile.
+        let advanced = self.rules.defender_advances && strike.is_some_and(|s| {
+            s.result == BattleResult::Loss && s.defender != Piece::Bomb
+        });
+
+        if mover == self.colour {
+            if let Some(i) = self.scouts.iter().position(|&c| c == mv.to) {
+                match result {
+                    Some(BattleResult::Loss) if advanced => self.scouts[i] = mv.from,
+                    Some(BattleResult::Loss) => (),
+                    _ => { self.scouts.remove(i); }
+                }
+            }
+            return
+        }
+
+        let known = self.scouts.iter().position(|&c| c == mv.from);
+        if let Some(i) = known {
+            self.scouts.remove(i);
+        }
+        let far = (mv.to.x as i32 - mv.from.x as i32).abs()
+            + (mv.to.y as i32 - mv.from.y as i32).abs() > 1;
+        let survived = matches!(result, None | Some(BattleResult::Victory));
+        if (known.is_some() || far) && survived {
+            self.scouts.push(mv.to);
+        }
+    }
+
+    /// Works out the chances again from what the player can see.
+    pub fn update(&mut self, view: &View) {
+        let geometry = self.rules.geometry;
+
+        // The ranks not yet accounted for by captures or reveals.
+        let mut left = [0.0; 12];
+        for &piece in self.rules.army.pieces() {
+            left[index(piece)] += 1.0;
+        }
+        for &piece in &view.enemy_captured {
+            left[index(piece)] -= 1.0;
+        }
+
+        for row in &mut self.odds {
+            for odds in row.iter_mut() {
+                *odds = [0.0; 12];
+            }
+        }
+        let mut hidden = vec![];
+        for c in geometry.coords() {
+            match view.tile_at(c) {
+                Seen::Enemy {rank: Some(rank), ..} => {
+                    left[index(rank)] -= 1.0;
+                    self.odds[c.y as usize][c.x as usize][index(rank)] = 1.0;
+                }
+                Seen::Enemy {rank: None, moved} => hidden.push((c, moved)),
+                _ => (),
+            }
+        }
+        for n in &mut left {
+            *n = f64::max(*n, 0.0);
+        }
+
+        // Start each piece off with a share of every rank it could be.
+        let mut shares = hidden
+            .iter()
+            .map(|&(c, moved)| {
+                let mut share = [0.0; 12];
+                for (r, &piece) in RANKS.iter().enumerate() {
+                    let possible = if self.scouts.contains(&c) {
+                        piece == Piece::Scout
+                    } else {
+                        !moved || (piece != Piece::Bomb && piece != Piece::Flag)
+                    };
+                    if possible {
+                        share[r] = left[r];
+                    }
+                }
+                share
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0 .. ROUNDS {
+            for share in &mut shares {
+                normalise(share);
+            }
+            for (r, &count) in left.iter().enumerate() {
+                let total = shares.iter().map(|s| s[r]).sum::<f64>();
+                if total > 0.0 {
+                    for share in &mut shares {
+                        share[r] *= count / total;
+                    }
+                }
+            }
+        }
+        for (&(c, _), share) in hidden.iter().zip(&mut shares) {
+            normalise(share);
+            self.odds[c.y as usize][c.x as usize] = *share;
+        }
+        self.left = left;
+    }
+
+    /// Guesses the rank of every hidden enemy piece from the odds, so that
+    /// together they make up what is left hidden of the enemy's army.
+    ///
+    /// The view must be the one last passed to `update`.
+    pub fn sample<R: Rng>(&self, view: &View, rng: &mut R) -> Vec<(Coord, Piece)> {
+        let mut left = self.left.iter().map(|&n| n.round() as usize).collect::<Vec<_>>();
+        let mut hidden = view.rules.geometry
+            .coords()
+            .filter(|&c| matches!(view.tile_at(c), Seen::Enemy {rank: None, ..}))
+            .collect::<Vec<_>>();
+        let mut guess = vec![];
+
+        // The flag is on the board for as long as the game goes on, so it is
+        // placed first, in case there are fewer hidden pieces than ranks left.
+        let flag = index(Piece::Flag);
+        let weights = hidden.iter().map(|&c| self.chance(c, Piece::Flag)).collect::<Vec<_>>();
+        if let (true, Some(n)) = (left[flag] > 0, pick(&weights, rng)) {
+            guess.push((hidden.remove(n), Piece::Flag));
+            left[flag] -= 1;
+        }
+
+        // The pieces that could be the fewest ranks go first, so that the
+        // ranks only they could be are still left for them.
+        hidden.sort_by_key(|&c| RANKS.iter().filter(|&&p| self.chance(c, p) > 0.0).count());
+        for c in hidden {
+            let odds = &self.odds[c.y as usize][c.x as usize];
+            let weights = (0 .. RANKS.len())
+                .map(|r| if left[r] > 0 { odds[r] } else { 0.0 })
+                .collect::<Vec<_>>();
+            // Nothing fits only if there are more hidden pieces than the army
+            // has left, as on a board set up by hand.
+            let r = pick(&weights, rng)
+                .or_else(|| left.iter().position(|&n| n > 0))
+                .unwrap_or(index(Piece::Scout));
+            left[r] = left[r].saturating_sub(1);
+            guess.push((c, RANKS[r]));
+        }
+        guess
+    }
+
+    /// The chance that the enemy piece on the tile is of the rank, or zero if
+    /// there is no enemy piece there.
+    pub fn chance(&self, c: Coord, piece: Piece) -> f64 {
+        self.odds[c.y as usize][c.x as usize][index(piece)]
+    }
+
+    /// Every rank the enemy piece on the tile might be, with its chance, most
+    /// likely first.
+    pub fn odds(&self, c: Coord) -> Vec<(Piece, f64)> {
+        let mut odds = RANKS
+            .iter()
+            .map(|&piece| (piece, self.chance(c, piece)))
+            .filter(|&(_, p)| p > 0.0)
+            .collect::<Vec<_>>();
+        odds.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        odds
+    }
+
+    /// The most likely rank of the enemy piece on the tile, if there is one.
+    pub fn likeliest(&self, c: Coord) -> Option<Piece> {
+        self.odds(c).first().map(|&(piece, _)| piece)
+    }
+}
+
+fn index(piece: Piece) -> usize {
+    RANKS.iter().position(|&p| p == piece).unwrap()
+}
+
+/// Picks an index at random, each as likely as its weight, or `None` if
+/// every weight is zero.
+fn pick<R: Rng>(weights: &[f64], rng: &mut R) -> Option<usize> {
+    let total = weights.iter().sum::<f64>();
+    if total <= 0.0 {
+        return None
+    }
+    let mut left = rng.gen::<f64>() * total;
+    let last = weights.iter().rposition(|&w| w > 0.0);
+    weights
+        .iter()
+        .position(|&w| {
+            left -= w;
+            w > 0.0 && left < 0.0
+        })
+        .or(last)
+}
+
+/// Scales the shares so that they add up to one, unless they are all zero.
+fn normalise(share: &mut [f64; 12]) {
+    let total = share.iter().sum::<f64>();
+    if total > 0.0 {
+        for s in share.iter_mut() {
+            *s /= total;
+        }
+    }
+}
@@ -1,6 +1,8 @@
-const DEFAULT_NO_MANS_LAND: [Tile; 10] =
-    [Tile::Empty, Tile::Empty,   Tile::Terrain, Tile::Terrain, Tile::Empty,
-     Tile::Empty, Tile::Terrain, Tile::Terrain, Tile::Empty,   Tile::Empty];
+use rand::Rng;
+
+use error::IllegalMove;
+use rules::{self, Numbering, Rules};
+use setup::Setup;
 
 const TERRAIN_DISP_CHAR: &str = "~";
 const HIDDEN_DISP_CHAR: &str = "▇";
@@ -22,10 +24,11 @@ pub enum Piece {
 }
 
 impl Piece {
-    fn from(s: &str) -> Option<Piece> {
+    pub fn from(s: &str) -> Option<Piece> {
         match s {
             "bomb"       | "b" | "B" => Some(Piece::Bomb),
-            "marshall"   | "1"       => Some(Piece::Marshall),
+            "marshall"   | "marshal"
+                         | "1"       => Some(Piece::Marshall),
             "general"    | "2"       => Some(Piece::General),
             "colonel"    | "3"       => Some(Piece::Colonel),
             "major"      | "4"       => Some(Piece::Major),
@@ -39,9 +42,79 @@ impl Piece {
             _                        => None,
         }
     }
+    /// Reads a piece from the symbol it is shown with under the numbering.
+    /// The modern marshall may be given as either X or 10.
+    pub fn from_symbol(s: &str, numbering: Numbering) -> Option<Piece> {
+        match numbering {
+            Numbering::Classic => match s {
+                "b" | "s" | "f" => Piece::from(&s.to_uppercase()),
+                "B" | "S" | "F" => Piece::from(s),
+                _ => s.parse::<u8>().ok().and_then(|_| Piece::from(s)),
+            },
+            Numbering::Modern => match s {
+                "B" | "b" => Some(Piece::Bomb),
+                "F" | "f" => Some(Piece::Flag),
+                "X" | "x" => Some(Piece::Marshall),
+                _ => {
+                    let rank = s.parse::<u8>().ok()?;
+                    [
+                        Piece::Spy, Piece::Scout, Piece::Miner,
+                        Piece::Sergeant, Piece::Lieutenant, Piece::Captain,
+                        Piece::Major, Piece::Colonel, Piece::General,
+                        Piece::Marshall,
+                    ].iter().cloned().find(|p| p.value() == rank)
+                }
+            },
+        }
+    }
+    /// The symbol the piece is shown with under the numbering.
+    pub fn symbol(&self, numbering: Numbering) -> &'static str {
+        use self::Piece::*;
+        match (numbering, *self) {
+            (_, Bomb)                         => "B",
+            (_, Flag)                         => "F",
+            (Numbering::Classic, Marshall)    => "1",
+            (Numbering::Classic, General)     => "2",
+            (Numbering::Classic, Colonel)     => "3",
+            (Numbering::Classic, Major)       => "4",
+            (Numbering::Classic, Captain)     => "5",
+            (Numbering::Classic, Lieutenant)  => "6",
+            (Numbering::Classic, Sergeant)    => "7",
+            (Numbering::Classic, Miner)       => "8",
+            (Numbering::Classic, Scout)       => "9",
+            (Numbering::Classic, Spy)         => "S",
+            (Numbering::Modern, Marshall)     => "X",
+            (Numbering::Modern, General)      => "9",
+            (Numbering::Modern, Colonel)      => "8",
+            (Numbering::Modern, Major)        => "7",
+            (Numbering::Modern, Captain)      => "6",
+            (Numbering::Modern, Lieutenant)   => "5",
+            (Numbering::Modern, Sergeant)     => "4",
+            (Numbering::Modern, Miner)        => "3",
+            (Numbering::Modern, Scout)        => "2",
+            (Numbering::Modern, Spy)          => "1",
+        }
+    }
+    /// The full name of the piece, as accepted by `Piece::from`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Piece::Bomb       => "bomb",
+            Piece::Marshall   => "marshall",
+            Piece::General    => "general",
+            Piece::Colonel    => "colonel",
+            Piece::Major      => "major",
+            Piece::Captain    => "captain",
+            Piece::Lieutenant => "lieutenant",
+            Piece::Sergeant   => "sergeant",
+            Piece::Miner      => "miner",
+            Piece::Scout      => "scout",
+            Piece::Spy        => "spy",
+            Piece::Flag       => "flag",
+        }
+    }
     fn value(&self) -> u8 {
         match *self {
-            Piece::Bomb       => ::std::u8::MAX,
+            Piece::Bomb       => u8::MAX,
             Piece::Marshall   => 10,
             Piece::General    => 9,
             Piece::Colonel    => 8,
@@ -58,7 +131,7 @@ impl Piece {
     pub fn attack(&self, other: Piece) -> BattleResult {
         use self::BattleResult::*;
         use std::cmp::Ordering::*;
-        if (self.value() == 3 && other.value() == ::std::u8::MAX)
+        if (self.value() == 3 && other.value() == u8::MAX)
             || (self.value() == 1 && other.value() == 10)
         {
             // Exceptions for miners able to capture bombs, and spies able
@@ -76,7 +149,7 @@ impl Piece {
 
 impl ::std::cmp::PartialOrd for Piece {
     fn partial_cmp(&self, other: &Piece) -> Option<::std::cmp::Ordering> {
-        Some(self.value().cmp(&other.value()))
+        Some(self.cmp(other))
     }
 }
 
@@ -86,23 +159,10 @@ impl ::std::cmp::Ord for Piece {
     }
 }
 
+/// Shows the piece with the classic numbering.
 impl ::std::fmt::Display for Piece {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        use self::Piece::*;
-        match *self {
-            Bomb       => write!(f, "B"),
-            Marshall   => write!(f, "1"),
-            General    => write!(f, "2"),
-            Colonel    => write!(f, "3"),
-            Major      => write!(f, "4"),
-            Captain    => write!(f, "5"),
-            Lieutenant => write!(f, "6"),
-            Sergeant   => write!(f, "7"),
-            Miner      => write!(f, "8"),
-            Scout      => write!(f, "9"),
-            Spy        => write!(f, "S"),
-            Flag       => write!(f, "F"),
-        }
+        write!(f, "{}", self.symbol(Numbering::Classic))
     }
 }
 
@@ -113,6 +173,87 @@ pub enum BattleResult {
     Draw
 }
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    Win(Colour, WinReason),
+    Draw(DrawReason),
+    Ongoing
+}
+
+impl ::std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            GameResult::Win(c, r) => write!(f, "{} wins ({})", c, r),
+            GameResult::Draw(r)   => write!(f, "draw ({})", r),
+            GameResult::Ongoing   => write!(f, "ongoing"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WinReason {
+    /// The loser's flag was captured.
+    FlagCaptured,
+    /// The loser had no legal moves on their turn.
+    NoMoves
+}
+
+impl WinReason {
+    /// Reads the reason back from its `Display` form.
+    pub fn from(s: &str) -> Option<WinReason> {
+        match s {
+            "flag captured" => Some(WinReason::FlagCaptured),
+            "no moves left" => Some(WinReason::NoMoves),
+            _               => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for WinReason {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            WinReason::FlagCaptured => write!(f, "flag captured"),
+            WinReason::NoMoves      => write!(f, "no moves left"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DrawReason {
+    /// The last movable pieces destroyed each other in battle.
+    MutualAnnihilation,
+    /// Neither player has any legal moves.
+    NoMoves,
+    /// The move limit set on the board was reached.
+    MoveLimit,
+    /// The same position occurred three times with the same player to move.
+    Repetition
+}
+
+impl DrawReason {
+    /// Reads the reason back from its `Display` form.
+    pub fn from(s: &str) -> Option<DrawReason> {
+        match s {
+            "mutual annihilation"  => Some(DrawReason::MutualAnnihilation),
+            "no moves left"        => Some(DrawReason::NoMoves),
+            "move limit reached"   => Some(DrawReason::MoveLimit),
+            "threefold repetition" => Some(DrawReason::Repetition),
+            _                      => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            DrawReason::MutualAnnihilation => write!(f, "mutual annihilation"),
+            DrawReason::NoMoves            => write!(f, "no moves left"),
+            DrawReason::MoveLimit          => write!(f, "move limit reached"),
+            DrawReason::Repetition         => write!(f, "threefold repetition"),
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Colour{
     Red,
@@ -127,6 +268,24 @@ impl Colour {
             Blue => Red,
         }
     }
+
+    /// A distinct index for each colour, for keeping per-player data in an
+    /// array.
+    pub fn index(&self) -> usize {
+        match *self {
+            Colour::Red  => 0,
+            Colour::Blue => 1,
+        }
+    }
+}
+
+impl ::std::fmt::Display for Colour {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            Colour::Red  => write!(f, "red"),
+            Colour::Blue => write!(f, "blue"),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -137,14 +296,14 @@ pub enum Tile {
 }
 
 impl Tile {
-    pub fn show(&self, viewer: Colour) -> String {
+    pub fn show(&self, viewer: Colour, numbering: Numbering) -> String {
         match *self {
-            Tile::Terrain     => format!("{}", TERRAIN_DISP_CHAR),
+            Tile::Terrain     => TERRAIN_DISP_CHAR.to_string(),
             Tile::Empty       => " ".to_string(),
             Tile::Piece(p, c) => if viewer == c {
-                format!("{}", p)
+                p.symbol(numbering).to_string()
             } else {
-                format!("{}", HIDDEN_DISP_CHAR)
+                HIDDEN_DISP_CHAR.to_string()
             }
         }
     }
@@ -161,6 +320,17 @@ impl ::std::fmt::Display for Tile {
     }
 }
 
+/// What is publicly known about a piece on the board, along with a number
+/// identifying the piece for the rest of the game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PieceInfo {
+    pub id:       u16,
+    /// Whether the piece's rank has been revealed in battle.
+    pub revealed: bool,
+    /// Whether the piece has ever moved, and so can't be a bomb or flag.
+    pub moved:    bool,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Coord {
     pub x: u16,
@@ -168,34 +338,120 @@ pub struct Coord {
 }
 
 impl Coord {
+    /// Reads a tile of the classic board, such as `e7` for the fifth column
+    /// and the eighth row down. Use `parse` for the tiles of other boards.
     pub fn from(s: &str) -> Option<Self> {
+        Coord::parse(s, Geometry::CLASSIC)
+    }
+
+    /// Reads a tile of the board, written as for `from`, or `None` if it is
+    /// off the board.
+    pub fn parse(s: &str, geometry: Geometry) -> Option<Self> {
         if s.len() != 2 {
             None
         } else {
             let s = s.chars().collect::<Vec<_>>();
             let (x, y) = (s[0], s[1]);
-            if x < 'a' || x > 'j' || y < '0' || y > '9' {
+            if !('a' ..= 'j').contains(&x) || !y.is_ascii_digit() {
                 None
             } else {
                 Some(Coord {
                     x: x.to_digit(20).unwrap() as u16 - 10,
                     y: y.to_digit(10).unwrap() as u16,
-                })
+                }).filter(|&c| geometry.contains(c))
             }
         }
     }
+}
 
-    pub fn offset(&self, x: isize, y: isize) -> Option<Self> {
-        let (mx, my) = (self.x as isize + x, self.y as isize + y);
-        if mx < 0 || my < 0 || mx > 9 || my > 9 {
-            None
-        } else {
-            Some(Coord {x: mx as u16, y: my as u16})
+/// The shape of the board a variant is played on.
+///
+/// Blue sets up along the top of the board and Red along the bottom, with
+/// the lakes in the rows between them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Geometry {
+    /// The name of the board, as used in variant names.
+    pub name:       &'static str,
+    pub width:      u16,
+    pub height:     u16,
+    /// The tiles no piece may enter.
+    pub lakes:      &'static [Coord],
+    /// How many rows deep each player's setup area is.
+    pub setup_rows: u16,
+}
+
+impl Geometry {
+    /// The classic 10x10 board, with two 2x2 lakes.
+    pub const CLASSIC: Geometry = Geometry {
+        name:       "classic",
+        width:      10,
+        height:     10,
+        lakes:      &[
+            Coord {x: 2, y: 4}, Coord {x: 3, y: 4}, Coord {x: 6, y: 4},
+            Coord {x: 7, y: 4}, Coord {x: 2, y: 5}, Coord {x: 3, y: 5},
+            Coord {x: 6, y: 5}, Coord {x: 7, y: 5},
+        ],
+        setup_rows: 4,
+    };
+
+    /// The 8x8 board of quick games, with two 1x2 lakes.
+    pub const DUEL: Geometry = Geometry {
+        name:       "duel",
+        width:      8,
+        height:     8,
+        lakes:      &[
+            Coord {x: 2, y: 3}, Coord {x: 5, y: 3}, Coord {x: 2, y: 4},
+            Coord {x: 5, y: 4},
+        ],
+        setup_rows: 3,
+    };
+
+    pub const ALL: [Geometry; 2] = [Geometry::CLASSIC, Geometry::DUEL];
+
+    pub fn from(name: &str) -> Option<Geometry> {
+        Geometry::ALL.iter().cloned().find(|g| g.name == name)
+    }
+
+    /// Whether the coordinate is on the board.
+    pub fn contains(&self, c: Coord) -> bool {
+        c.x < self.width && c.y < self.height
+    }
+
+    /// The coordinate offset from `c`, if it is still on the board.
+    pub fn offset(&self, c: Coord, x: isize, y: isize) -> Option<Coord> {
+        let (mx, my) = (c.x as isize + x, c.y as isize + y);
+        if mx < 0 || my < 0 {
+            return None
+        }
+        Some(Coord {x: mx as u16, y: my as u16}).filter(|&c| self.contains(c))
+    }
+
+    /// Every coordinate on the board, row by row from the top.
+    pub fn coords(&self) -> impl Iterator<Item = Coord> {
+        let width = self.width;
+        (0 .. self.height).flat_map(move |y| (0 .. width).map(move |x| Coord {x, y}))
+    }
+
+    /// The number of squares in each player's setup area.
+    pub fn setup_squares(&self) -> usize {
+        self.width as usize * self.setup_rows as usize
+    }
+
+    /// The board coordinate of a row and column of the colour's setup.
+    ///
+    /// Rows run from the player's back row to their front line, and columns
+    /// from the player's left to their right, so that the players face each
+    /// other.
+    pub fn setup_coord(&self, colour: Colour, row: usize, col: usize) -> Coord {
+        let (row, col) = (row as u16, col as u16);
+        match colour {
+            Colour::Red  => Coord {x: col, y: self.height - 1 - row},
+            Colour::Blue => Coord {x: self.width - 1 - col, y: row},
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Move {
     pub from: Coord,
     pub to: Coord
@@ -206,31 +462,116 @@ impl Move {
     pub fn reverse(&self) -> Self {Move {from: self.to, to: self.from}}
 }
 
-#[derive(Debug)]
+/// A battle between two pieces. Both pieces are revealed to both players.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Strike {
+    pub attacker: Piece,
+    pub defender: Piece,
+    pub result:   BattleResult,
+}
+
+/// The effects of a move once it has been played on the board.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub mv:     Move,
+    pub piece:  Piece,
+    pub colour: Colour,
+    pub strike: Option<Strike>,
+    /// Whether the defender won the battle and moved onto the tile the
+    /// attacker came from.
+    pub advanced: bool,
+}
+
+impl Outcome {
+    /// The pieces revealed by the move, and the tiles they stood on. A
+    /// defender that advanced is given on the tile it moved onto.
+    pub fn revealed(&self) -> Vec<(Coord, Piece, Colour)> {
+        match self.strike {
+            Some(s) => vec![
+                (self.mv.from, s.attacker, self.colour),
+                (
+                    if self.advanced { self.mv.from } else { self.mv.to },
+                    s.defender,
+                    self.colour.other(),
+                ),
+            ],
+            None => vec![],
+        }
+    }
+
+    /// The pieces removed from the board by the move, and the tiles they
+    /// stood on.
+    pub fn casualties(&self) -> Vec<(Coord, Piece, Colour)> {
+        use self::BattleResult::*;
+        let s = match self.strike {
+            Some(s) => s,
+            None => return vec![],
+        };
+        let attacker = (self.mv.from, s.attacker, self.colour);
+        let defender = (self.mv.to, s.defender, self.colour.other());
+        match s.result {
+            Victory => vec![defender],
+            Loss    => vec![attacker],
+            Draw    => vec![attacker, defender],
+        }
+    }
+
+    /// Where the surviving piece ended up, or `None` if no piece survived.
+    pub fn survivor(&self) -> Option<Coord> {
+        match self.strike {
+            Some(Strike { result: BattleResult::Draw, .. }) => None,
+            _ if self.advanced => Some(self.mv.from),
+            _ => Some(self.mv.to),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Board {
-    board: [[Tile; 10]; 10],
-    moves: Vec<Move>
+    board:      Vec<Vec<Tile>>,
+    /// What is known about the piece on each tile, if any.
+    info:       Vec<Vec<Option<PieceInfo>>>,
+    next_id:    u16,
+    moves:      Vec<Outcome>,
+    /// The position before the first move, followed by the position after
+    /// each move.
+    positions:  Vec<Vec<Vec<Tile>>>,
+    /// The number of moves played before the board was set up, if it was
+    /// set up partway through a game.
+    earlier:    usize,
+    move_limit: Option<usize>,
+    rules:      Rules,
 }
 
 impl Board {
-    pub fn new() -> Self {
+    pub fn new() -> Self {Board::with_rules(Rules::default())}
+
+    /// A new, empty board, playing by the given rules.
+    pub fn with_rules(rules: Rules) -> Self {
+        let geometry = rules.geometry;
+        let (width, height) = (geometry.width as usize, geometry.height as usize);
+        let mut board = vec![vec![Tile::Empty; width]; height];
+        for c in geometry.lakes {
+            board[c.y as usize][c.x as usize] = Tile::Terrain;
+        }
         Board {
-            board: [
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-                DEFAULT_NO_MANS_LAND,
-                DEFAULT_NO_MANS_LAND,
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-                [Tile::Empty; 10],
-            ],
-            moves: vec![]
+            board,
+            info:       vec![vec![None; width]; height],
+            next_id:    0,
+            moves:      vec![],
+            positions:  vec![],
+            earlier:    0,
+            move_limit: None,
+            rules,
         }
     }
 
+    /// The rules the game is played by.
+    pub fn rules(&self) -> Rules {self.rules}
+
+    /// The shape of the board.
+    pub fn geometry(&self) -> Geometry {self.rules.geometry}
+
     pub fn tile_at(&self, c: Coord) -> Tile {
         self.board[c.y as usize][c.x as usize]
     }
@@ -238,26 +579,230 @@ impl Board {
     // fn adjacent(&self, c: Coord) -> Vec<Coord> {
     //     let mut adj = vec![];
     //     for &(x, y) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
-    //         if let Some(c_next) = c.offset(x, y) {
+    //         if let Some(c_next) = self.geometry().offset(c, x, y) {
     //             adj.push(c_next)
     //         }
     //     }
     //     adj
     // }
 
+    /// What is known about the piece on the tile, or `None` if there is no
+    /// piece there.
+    pub fn info_at(&self, c: Coord) -> Option<PieceInfo> {
+        self.info[c.y as usize][c.x as usize]
+    }
+
+    /// Sets the tile. A piece placed this way is treated as a new piece,
+    /// which has never moved or been revealed.
     pub fn set_tile(&mut self, c: Coord, t: Tile) {
         self.board[c.y as usize][c.x as usize] = t;
+        self.info[c.y as usize][c.x as usize] = match t {
+            Tile::Piece(..) => {
+                self.next_id += 1;
+                Some(PieceInfo {
+                    id:       self.next_id,
+                    revealed: false,
+                    moved:    false,
+                })
+            }
+            _ => None,
+        };
     }
 
-    /// Mutates the game state with the provided move.
+    /// Shows the tile as seen by the player, with enemy pieces hidden unless
+    /// they have been revealed.
+    pub fn show(&self, c: Coord, viewer: Colour) -> String {
+        match (self.tile_at(c), self.info_at(c)) {
+            (Tile::Piece(p, _), Some(PieceInfo {revealed: true, ..})) =>
+                p.symbol(self.rules.numbering).to_string(),
+            (t, _) => t.show(viewer, self.rules.numbering),
+        }
+    }
+
+    /// Sets the number of moves (counting both players) after which the game
+    /// is drawn, or `None` for no limit.
+    pub fn set_move_limit(&mut self, limit: Option<usize>) {
+        self.move_limit = limit;
+    }
+
+    /// The colour whose turn it is. Red always moves first.
+    pub fn to_move(&self) -> Colour {
+        match (self.earlier + self.moves.len()) % 2 {
+            0 => Colour::Red,
+            _ => Colour::Blue,
+        }
+    }
+
+    /// Sets whose turn it is, for a board set up partway through a game.
+    pub fn set_to_move(&mut self, colour: Colour) {
+        if self.to_move() != colour {
+            self.earlier += 1;
+        }
+    }
+
+    /// Checks whether a move may be played in the current game state.
+    ///
+    /// Unlike `find_moves`, this gives the reason a move was rejected, so that
+    /// moves from untrusted sources can be handed straight to the board.
+    pub fn check_move(&self, m: &Move) -> Result<(), IllegalMove> {
+        if !self.geometry().contains(m.from) || !self.geometry().contains(m.to) {
+            return Err(IllegalMove::OutOfBounds)
+        }
+
+        let (piece, colour) = match self.tile_at(m.from) {
+            Tile::Empty       => return Err(IllegalMove::EmptyTile),
+            Tile::Terrain     => return Err(IllegalMove::Terrain),
+            Tile::Piece(p, c) => (p, c),
+        };
+        if colour != self.to_move() {
+            return Err(IllegalMove::OutOfTurn(self.to_move()))
+        }
+        if piece == Piece::Bomb || piece == Piece::Flag {
+            return Err(IllegalMove::Immobile(piece))
+        }
+
+        match self.tile_at(m.to) {
+            Tile::Terrain => return Err(IllegalMove::IntoTerrain),
+            Tile::Piece(_, c) if c == colour =>
+                return Err(IllegalMove::AlliedPiece),
+            _ => (),
+        }
+
+        let dx = m.to.x as isize - m.from.x as isize;
+        let dy = m.to.y as isize - m.from.y as isize;
+        if piece != Piece::Scout {
+            if dx.abs() + dy.abs() != 1 {
+                return Err(IllegalMove::NotAdjacent)
+            }
+        } else {
+            // Scouts may travel any distance, but only in a straight line over
+            // empty tiles.
+            if (dx == 0) == (dy == 0) {
+                return Err(IllegalMove::InvalidScoutPath)
+            }
+            let dist = dx.abs().max(dy.abs());
+            let (sx, sy) = (dx.signum(), dy.signum());
+            for n in 1 .. dist {
+                let c = self.geometry().offset(m.from, sx * n, sy * n).unwrap();
+                if self.tile_at(c) != Tile::Empty {
+                    return Err(IllegalMove::InvalidScoutPath)
+                }
+            }
+            let strike = matches!(self.tile_at(m.to), Tile::Piece(..));
+            if strike && dist > 1 && !self.rules.scout_strikes {
+                return Err(IllegalMove::ScoutStrike)
+            }
+        }
+
+        if rules::breaks_two_square(&self.moves, m, colour) {
+            return Err(IllegalMove::TwoSquareRule)
+        }
+        if rules::breaks_more_square(&self.moves, m, colour) {
+            return Err(IllegalMove::MoreSquareRule)
+        }
+        Ok(())
+    }
+
+    /// Plays the provided move, if it is legal, resolving any battle.
+    pub fn try_move(&mut self, m: Move) -> Result<Outcome, IllegalMove> {
+        self.check_move(&m)?;
+        Ok(self.apply_move(m))
+    }
+
+    /// The outcomes of every move played so far, oldest first.
+    pub fn history(&self) -> &[Outcome] {&self.moves}
+
+    /// A new board with the position from before the first move was played,
+    /// with the same colour to move.
+    pub fn initial(&self) -> Board {
+        let mut board = Board::with_rules(self.rules);
+        board.move_limit = self.move_limit;
+        board.earlier = self.earlier;
+        let start = self.positions.first().unwrap_or(&self.board);
+        for c in self.geometry().coords() {
+            board.set_tile(c, start[c.y as usize][c.x as usize]);
+        }
+        board
+    }
+
+    /// Mutates the game state with the provided move, resolving any battle.
     ///
     /// There is **no legality checking** in this function. Use `find_moves`
-    /// to present a list of legal moves before mutating the game state.
-    pub fn apply_move(&mut self, m: Move) {
-        let _old = self.tile_at(m.from);
-        self.set_tile(m.to, _old);
-        self.set_tile(m.from, Tile::Empty);
-        self.moves.push(m);
+    /// to present a list of legal moves before mutating the game state, or
+    /// `try_move` to have the move checked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no piece on the tile being moved from.
+    pub fn apply_move(&mut self, m: Move) -> Outcome {
+        use self::BattleResult::*;
+
+        let (piece, colour) = match self.tile_at(m.from) {
+            Tile::Piece(p, c) => (p, c),
+            t => panic!("Cannot move from {:?} tile at {:?}", t, m.from),
+        };
+        let strike = match self.tile_at(m.to) {
+            Tile::Piece(defender, _) => Some(Strike {
+                attacker: piece,
+                defender,
+                result:   piece.attack(defender),
+            }),
+            _ => None,
+        };
+
+        if self.positions.is_empty() {
+            self.positions.push(self.board.clone());
+        }
+
+        // Move the pieces directly, rather than with `set_tile`, so that
+        // they keep their identity.
+        let (fx, fy) = (m.from.x as usize, m.from.y as usize);
+        let (tx, ty) = (m.to.x as usize, m.to.y as usize);
+        let moving = self.board[fy][fx];
+        let mut mover = self.info[fy][fx].take();
+        self.board[fy][fx] = Tile::Empty;
+        if let Some(ref mut i) = mover {
+            i.moved = true;
+            i.revealed |= strike.is_some();
+        }
+        let mut advanced = false;
+        match strike.map(|s| s.result) {
+            None | Some(Victory) => {
+                self.board[ty][tx] = moving;
+                self.info[ty][tx] = mover;
+            }
+            Some(Loss) => {
+                if let Some(ref mut i) = self.info[ty][tx] {
+                    i.revealed = true;
+                }
+                // Bombs stay where they are.
+                let bomb = strike.is_some_and(|s| s.defender == Piece::Bomb);
+                if self.rules.defender_advances && !bomb {
+                    self.board[fy][fx] = self.board[ty][tx];
+                    self.info[fy][fx] = self.info[ty][tx].take();
+                    if let Some(ref mut i) = self.info[fy][fx] {
+                        i.moved = true;
+                    }
+                    self.board[ty][tx] = Tile::Empty;
+                    advanced = true;
+                }
+            }
+            Some(Draw) => {
+                self.board[ty][tx] = Tile::Empty;
+                self.info[ty][tx] = None;
+            }
+        }
+
+        let outcome = Outcome {
+            mv:     m,
+            piece,
+            colour,
+            strike,
+            advanced,
+        };
+        self.moves.push(outcome);
+        self.positions.push(self.board.clone());
+        outcome
     }
 
     /// Finds all legal moves available from the coordinate.
@@ -269,6 +814,22 @@ impl Board {
     /// The list may contain more than four moves if the tile contains a scout,
     /// as they may move any number of spaces in an unbroken line.
     pub fn find_moves(&self, c: Coord) -> Vec<Move> {
+        let mut mvs = self.reachable(c);
+
+        // Filter out moves that would break the rules on repetition.
+        if let Tile::Piece(_, curr_col) = self.tile_at(c) {
+            mvs.retain(|m| {
+                !rules::breaks_two_square(&self.moves, m, curr_col)
+                    && !rules::breaks_more_square(&self.moves, m, curr_col)
+            });
+        }
+        mvs
+    }
+
+    /// Finds all moves from the coordinate that the piece can physically
+    /// make, ignoring the rules on repetition.
+    fn reachable(&self, c: Coord) -> Vec<Move> {
+        let geometry = self.geometry();
         let mut mvs = Vec::new();
         match self.tile_at(c) {
             Tile::Piece(curr_piece, curr_col) => {
@@ -279,23 +840,25 @@ impl Board {
                         // Iterate through the neighbours.
                         for &(x, y) in &[(1, 0), (-1, 0), (0, 1), (0, -1)]
                         {
-                            if let Some(next_c) = c.offset(x, y) {
+                            if let Some(next_c) = geometry.offset(c, x, y) {
                                 match self.tile_at(next_c) {
-                                    Tile::Piece(_, next_col) => {
-                                        if curr_col != next_col {
-                                            mvs.push(Move::new(c, next_c));
-                                        }
+                                    Tile::Piece(_, next_col)
+                                        if curr_col != next_col =>
+                                    {
+                                        mvs.push(Move::new(c, next_c));
                                     }
                                     // Go as far as possible.
                                     Tile::Empty => {
                                         mvs.push(Move::new(c, next_c));
                                         let mut mult = 2;
                                         'EXT: while let Some(next_c) =
-                                            c.offset(x * mult, y * mult)
+                                            geometry.offset(c, x * mult, y * mult)
                                         {
                                             match self.tile_at(next_c) {
                                                 Tile::Piece(_, next_col) => {
-                                                    if curr_col != next_col {
+                                                    if curr_col != next_col
+                                                        && self.rules.scout_strikes
+                                                    {
                                                         mvs.push(Move::new(c, next_c));
                                                     }
                                                     break 'EXT
@@ -316,12 +879,12 @@ impl Board {
                         // Iterate through the neighbours.
                         for &(x, y) in &[(1, 0), (-1, 0), (0, 1), (0, -1)]
                         {
-                            if let Some(next_c) = c.offset(x, y) {
+                            if let Some(next_c) = geometry.offset(c, x, y) {
                                 match self.tile_at(next_c) {
-                                    Tile::Piece(_, next_col) => {
-                                        if curr_col != next_col {
-                                            mvs.push(Move::new(c, next_c));
-                                        }
+                                    Tile::Piece(_, next_col)
+                                        if curr_col != next_col =>
+                                    {
+                                        mvs.push(Move::new(c, next_c));
                                     }
                                     Tile::Empty => mvs.push(Move::new(c, next_c)),
                                     _ => (),
@@ -336,26 +899,92 @@ impl Board {
         }
     }
 
+    /// Finds all legal moves available to the player's pieces.
+    pub fn find_all_moves(&self, player: Colour) -> Vec<Move> {
+        let mut mvs = Vec::new();
+        for c in self.geometry().coords() {
+            match self.tile_at(c) {
+                Tile::Piece(_, col) if col == player =>
+                    mvs.extend(self.find_moves(c)),
+                _ => (),
+            }
+        }
+        mvs
+    }
+
+    /// Determines whether the game has finished, and how.
+    ///
+    /// A player loses when their flag is captured, or when it is their turn
+    /// and they have no legal moves (either because all of their movable
+    /// pieces have been captured, or the survivors are walled in).
+    ///
+    /// The game is drawn when neither player can move, when the same
+    /// position comes up for the third time, or when the move limit is
+    /// reached.
+    pub fn result(&self) -> GameResult {
+        let mut flags = (false, false);
+        for line in &self.board {
+            for tile in line.iter() {
+                match *tile {
+                    Tile::Piece(Piece::Flag, Colour::Red)  => flags.0 = true,
+                    Tile::Piece(Piece::Flag, Colour::Blue) => flags.1 = true,
+                    _ => (),
+                }
+            }
+        }
+
+        match flags {
+            (false, _) =>
+                return GameResult::Win(Colour::Blue, WinReason::FlagCaptured),
+            (_, false) =>
+                return GameResult::Win(Colour::Red, WinReason::FlagCaptured),
+            _ => (),
+        }
+
+        let player = self.to_move();
+        if self.find_all_moves(player).is_empty() {
+            if !self.find_all_moves(player.other()).is_empty() {
+                return GameResult::Win(player.other(), WinReason::NoMoves)
+            }
+            let last_battle = self.moves.last().and_then(|o| o.strike);
+            return match last_battle {
+                Some(Strike { result: BattleResult::Draw, .. }) =>
+                    GameResult::Draw(DrawReason::MutualAnnihilation),
+                _ => GameResult::Draw(DrawReason::NoMoves),
+            }
+        }
+
+        if rules::repetitions(&self.positions) >= 3 {
+            GameResult::Draw(DrawReason::Repetition)
+        } else if self.move_limit.is_some_and(|l| self.moves.len() >= l) {
+            GameResult::Draw(DrawReason::MoveLimit)
+        } else {
+            GameResult::Ongoing
+        }
+    }
+
     /// Returns a formatted game state.
     ///
     /// Will blank out pieces that the player provided doesn't own, as it is
-    /// considered personal knowledge.
+    /// considered personal knowledge, unless they have been revealed.
     pub fn display_to(
         &self,
         player: Colour
     ) -> Result<String, ::std::fmt::Error> {
         use std::fmt::Write;
 
+        let geometry = self.geometry();
+        let bar = "───".repeat(geometry.width as usize);
         let mut s = String::new();
-        write!(s, "┌──────────────────────────────┐\n")?;
-        for line in &self.board {
+        writeln!(s, "┌{}┐", bar)?;
+        for y in 0 .. geometry.height {
             write!(s, "│")?;
-            for tile in line.iter() {
-                write!(s, " {} ", tile.show(player))?;
+            for x in 0 .. geometry.width {
+                write!(s, " {} ", self.show(Coord {x, y}, player))?;
             }
-            write!(s, "│\n")?;
+            writeln!(s, "│")?;
         }
-        write!(s, "└──────────────────────────────┘")?;
+        write!(s, "└{}┘", bar)?;
         Ok(s)
     }
 
@@ -363,97 +992,32 @@ impl Board {
     ///
     /// Randomises the placement of the starting pieces on the given side (where
     /// blue is the top half, and red is the bottom half)
-    pub fn randomise(&mut self, player: Colour) {
-        use self::Piece::*;
-        use rand::Rng;
-
-        let mut rng = ::rand::thread_rng();
-        // FIXME Make this less obvious?
-        let mut to_place = vec![
-            Bomb, Bomb, Bomb, Bomb, Bomb, Bomb, Marshall, General, Colonel,
-            Colonel, Major, Major, Major, Captain, Captain, Captain, Captain,
-            Lieutenant, Lieutenant, Lieutenant, Lieutenant, Sergeant, Sergeant,
-            Sergeant, Sergeant, Miner, Miner, Miner, Miner, Miner, Scout, Scout,
-            Scout, Scout, Scout, Scout, Scout, Scout, Spy, Flag
-        ];
-
-        {
-            let mut sl = to_place.as_mut_slice();
-            rng.shuffle(&mut sl);
-        }
-
-        let offset = match player {
-            Colour::Red => 6,
-            Colour::Blue => 0,
-        };
+    pub fn randomise<R: Rng>(&mut self, player: Colour, rng: &mut R) {
+        self.place(player, &Setup::random(self.rules, rng));
+    }
 
-        for x in 0 .. 10 {
-            for y in 0 .. 4 {
-                let coord = Coord {x: x, y: y + offset};
-                let piece =
-                    to_place.pop().expect("Unexpected end of placement list");
-                let tile = Tile::Piece(piece, player);
-                self.set_tile(coord, tile);
+    /// Places the player's pieces at the start of the game, clearing the
+    /// rest of their setup area.
+    pub fn place(&mut self, player: Colour, setup: &Setup) {
+        for (r, row) in setup.rows.iter().enumerate() {
+            for (c, square) in row.iter().enumerate() {
+                let tile = match *square {
+                    Some(piece) => Tile::Piece(piece, player),
+                    None => Tile::Empty,
+                };
+                self.set_tile(self.geometry().setup_coord(player, r, c), tile);
             }
         }
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {Board::new()}
+}
+
 impl<'a> ::std::iter::IntoIterator for &'a Board {
-    type Item = &'a [Tile; 10];
-    type IntoIter = ::std::slice::Iter<'a, [Tile; 10]>;
+    type Item = &'a Vec<Tile>;
+    type IntoIter = ::std::slice::Iter<'a, Vec<Tile>>;
 
     fn into_iter(self) -> Self::IntoIter {self.board.iter()}
 }
-
-fn create_side(colour: Colour) -> [[Tile; 10]; 4] {
-    match colour {
-        Colour::Red => unimplemented!(),
-        // Default board
-        Colour::Blue => DEFAULT_BLUE_SIDE,
-    }
-}
-const DEFAULT_BLUE_SIDE: [[Tile; 10]; 4] =
-    [[Tile::Piece(Piece::Miner,      Colour::Blue),
-      Tile::Piece(Piece::Captain,    Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Lieutenant, Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Miner,      Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Sergeant,   Colour::Blue),
-      Tile::Piece(Piece::Miner,      Colour::Blue),
-      Tile::Piece(Piece::Captain,    Colour::Blue)],
-
-     [Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Miner,      Colour::Blue),
-      Tile::Piece(Piece::Captain,    Colour::Blue),
-      Tile::Piece(Piece::Major,      Colour::Blue),
-      Tile::Piece(Piece::Sergeant,   Colour::Blue),
-      Tile::Piece(Piece::Spy,        Colour::Blue),
-      Tile::Piece(Piece::Flag,       Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Major,      Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue)],
-
-     [Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Colonel,    Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::Sergeant,   Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::General,    Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::Miner,      Colour::Blue),
-      Tile::Piece(Piece::Lieutenant, Colour::Blue)],
-
-     [Tile::Piece(Piece::Lieutenant, Colour::Blue),
-      Tile::Piece(Piece::Scout,      Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::Marshall,   Colour::Blue),
-      Tile::Piece(Piece::Lieutenant, Colour::Blue),
-      Tile::Piece(Piece::Captain,    Colour::Blue),
-      Tile::Piece(Piece::Major,      Colour::Blue),
-      Tile::Piece(Piece::Bomb,       Colour::Blue),
-      Tile::Piece(Piece::Colonel,    Colour::Blue),
-      Tile::Piece(Piece::Sergeant,   Colour::Blue)]];
@@ -0,0 +1,387 @@
+//! Computer players, which only see what their own colour can see of the
+//! game.
+
+use std::fmt;
+use std::time::Duration;
+
+use belief::Belief;
+use board::{BattleResult, Colour, Coord, Move, Piece, Strike};
+use error::{BotError, Error, Result};
+use expectimax::Expectimax;
+use ismcts::Ismcts;
+use player::Player;
+use random::{self, GameRng, Rng};
+use rules::Rules;
+use setup::Setup;
+use view::{Seen, View};
+
+/// How long a searching player may think about each move.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Budget {
+    /// A fixed number of rounds of the search, which always plays the same
+    /// way for the same seed. A round is one playout for `Ismcts`, the only
+    /// player to search in rounds.
+    Iterations(usize),
+    /// A fixed number of moves ahead, which always plays the same way for
+    /// the same seed. Only `Expectimax` searches to a depth.
+    Depth(usize),
+    /// As many rounds, or moves ahead, as fit in the time. How far the
+    /// search gets depends on the machine it runs on, so a game played with
+    /// a time budget can't be replayed from its seed.
+    Time(Duration),
+}
+
+impl Budget {
+    /// Reads a number of rounds, a number of moves ahead followed by `ply`,
+    /// or a number of milliseconds followed by `ms`.
+    pub fn from(s: &str) -> Option<Budget> {
+        if let Some(ms) = s.strip_suffix("ms") {
+            ms.parse().ok().map(|ms| Budget::Time(Duration::from_millis(ms)))
+        } else if let Some(depth) = s.strip_suffix("ply") {
+            depth.parse().ok().map(Budget::Depth)
+        } else {
+            s.parse().ok().map(Budget::Iterations)
+        }
+    }
+}
+
+impl fmt::Display for Budget {
+    /// Writes the budget as `Budget::from` reads it.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Budget::Iterations(n) => write!(f, "{}", n),
+            Budget::Depth(n) => write!(f, "{}ply", n),
+            Budget::Time(time) => write!(f, "{}ms", time.as_millis()),
+        }
+    }
+}
+
+/// A player that sets up at random and plays a random legal move each turn.
+///
+/// It is the weakest possible opponent, for practice and as a baseline to
+/// test stronger players against.
+#[derive(Debug)]
+pub struct Random {
+    rng: GameRng,
+}
+
+impl Random {
+    /// A player whose choices are all drawn from the seed.
+    pub fn new(seed: u64) -> Self {
+        Random { rng: random::seeded(seed) }
+    }
+}
+
+impl Player for Random {
+    fn name(&self) -> String {"random".to_string()}
+
+    fn setup(&mut self, _colour: Colour, rules: Rules) -> Result<Setup> {
+        Ok(Setup::random(rules, &mut self.rng))
+    }
+
+    /// Picks uniformly among every legal move of every piece. Gives up with
+    /// `Error::EarlyExit` if there are none, which only happens once the game
+    /// is over.
+    fn choose_move(&mut self, view: &View) -> Result<Move> {
+        self.rng.choose(view.moves()).cloned().ok_or(Error::EarlyExit)
+    }
+}
+
+/// How well a `Heuristic` player plays.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub enum Level {
+    /// Often plays a random move, and doesn't look out for threats.
+    Easy,
+    /// Keeps its pieces away from known stronger enemies.
+    Medium,
+    /// Also defends its flag against nearby enemies, and rarely varies from
+    /// the best move it finds.
+    Hard,
+}
+
+impl Level {
+    pub const ALL: [Level; 3] = [Level::Easy, Level::Medium, Level::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Level::Easy   => "easy",
+            Level::Medium => "medium",
+            Level::Hard   => "hard",
+        }
+    }
+
+    pub fn from(s: &str) -> Option<Level> {
+        Level::ALL.iter().cloned().find(|l| l.name() == s)
+    }
+
+    /// The chance of playing a random move instead of the best one.
+    fn blunder_rate(&self) -> f64 {
+        match *self {
+            Level::Easy   => 0.3,
+            Level::Medium => 0.1,
+            Level::Hard   => 0.0,
+        }
+    }
+
+    /// The most that is randomly added to each move's score, so that play
+    /// varies between moves of about the same worth.
+    fn noise(&self) -> i32 {
+        match *self {
+            Level::Easy   => 30,
+            Level::Medium => 10,
+            Level::Hard   => 3,
+        }
+    }
+}
+
+/// A player that follows rules of thumb, scoring each legal move by what it
+/// can see of the board and playing the best.
+///
+/// It attacks pieces it knows it can beat and keeps away from ones it knows
+/// it can't, sends miners after pieces that have never moved in case they are
+/// bombs, only uses its spy against a revealed marshall, and probes unknown
+/// pieces with scouts. Other attacks on hidden pieces are weighed by its
+/// belief of what they might be. It sets up with its flag on the back row,
+/// behind its bombs.
+#[derive(Debug)]
+pub struct Heuristic {
+    level:  Level,
+    rng:    GameRng,
+    /// What the player believes the enemy's hidden pieces are.
+    belief: Option<Belief>,
+}
+
+impl Heuristic {
+    /// A player of the level, whose choices are all drawn from the seed.
+    pub fn new(level: Level, seed: u64) -> Self {
+        Heuristic { level, rng: random::seeded(seed), belief: None }
+    }
+
+    /// How good the move looks, from what the player can see.
+    fn score(&self, view: &View, m: &Move) -> i32 {
+        let piece = match view.tile_at(m.from) {
+            Seen::Own {piece, ..} => piece,
+            _ => return i32::MIN,
+        };
+
+        let mut score = match view.tile_at(m.to) {
+            Seen::Enemy {rank: Some(rank), ..} => battle(piece, rank),
+            Seen::Enemy {rank: None, moved} => {
+                let odds = self.belief.as_ref().map_or(vec![], |b| b.odds(m.to));
+                gamble(piece, moved, &odds)
+            }
+            // Make progress towards the enemy's side.
+            _ => forward(view.colour, m) * 2,
+        };
+
+        if self.level >= Level::Medium {
+            if threatened(view, m.to, piece) {
+                score -= worth(piece);
+            } else if threatened(view, m.from, piece) {
+                score += worth(piece) / 2;
+            }
+        }
+
+        if self.level >= Level::Hard {
+            score += defence(view, m);
+        }
+        score
+    }
+}
+
+impl Player for Heuristic {
+    fn name(&self) -> String {format!("heuristic {}", self.level.name())}
+
+    fn setup(&mut self, colour: Colour, rules: Rules) -> Result<Setup> {
+        self.belief = Some(Belief::new(colour, rules));
+        Ok(guarded_setup(rules, &mut self.rng))
+    }
+
+    fn choose_move(&mut self, view: &View) -> Result<Move> {
+        self.belief
+            .get_or_insert_with(|| Belief::new(view.colour, view.rules))
+            .update(view);
+        if self.rng.gen::<f64>() < self.level.blunder_rate() {
+            return self.rng.choose(view.moves()).cloned().ok_or(Error::EarlyExit)
+        }
+        let noise = self.level.noise();
+        let mut best = None;
+        for m in view.moves() {
+            let score = self.score(view, m) + self.rng.gen_range(0, noise + 1);
+            if best.is_none_or(|(s, _)| score > s) {
+                best = Some((score, *m));
+            }
+        }
+        best.map(|(_, m)| m).ok_or(Error::EarlyExit)
+    }
+
+    fn observe(&mut self, mv: Move, strike: Option<Strike>) {
+        if let Some(ref mut belief) = self.belief {
+            belief.observe(mv, strike);
+        }
+    }
+}
+
+/// The computer player with the name: `random`, `easy`, `medium` or `hard`
+/// for a `Heuristic` player of that level, or `ismcts` or `expectimax` for a
+/// player searching within the budget, or its own default budget if none is
+/// given. Its choices are all drawn from the seed.
+///
+/// Returns `Ok(None)` if there is no player with the name, and an error if
+/// the player can't search within the budget.
+pub fn named(
+    name: &str,
+    seed: u64,
+    budget: Option<Budget>
+) -> ::std::result::Result<Option<Box<dyn Player>>, BotError> {
+    Ok(match name {
+        "random" => Some(Box::new(Random::new(seed))),
+        "ismcts" => Some(Box::new(Ismcts::new(budget.unwrap_or(Ismcts::BUDGET), seed)?)),
+        "expectimax" => Some(Box::new(Expectimax::new(budget.unwrap_or(Expectimax::BUDGET), seed)?)),
+        _ => Level::from(name).map(|level| Box::new(Heuristic::new(level, seed)) as Box<dyn Player>),
+    })
+}
+
+/// A setup with the flag somewhere on the back row, with bombs in front of
+/// and beside it while there are any, and the rest of the army at random.
+pub fn guarded_setup<R: Rng>(rules: Rules, rng: &mut R) -> Setup {
+    let geometry = rules.geometry;
+    let (rows, cols) = (geometry.setup_rows as usize, geometry.width as usize);
+    let mut pieces = rules.army.pieces().to_vec();
+    let mut squares = vec![None; rows * cols];
+
+    // Bombs go in front of the flag first, then beside it.
+    let flag = rng.gen_range(0, cols);
+    let mut guards = vec![];
+    if flag + 1 < cols {
+        guards.push(flag + 1);
+    }
+    if flag > 0 {
+        guards.push(flag - 1);
+    }
+    guards.push(cols + flag);
+    for piece in &[Piece::Flag, Piece::Bomb, Piece::Bomb, Piece::Bomb] {
+        let square = match *piece {
+            Piece::Flag => Some(flag),
+            _ => guards.pop(),
+        };
+        let index = pieces.iter().position(|p| p == piece);
+        if let (Some(square), Some(index)) = (square, index) {
+            squares[square] = Some(pieces.remove(index));
+        }
+    }
+
+    let mut empty = (0 .. squares.len())
+        .filter(|&n| squares[n].is_none())
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut empty);
+    for (n, piece) in empty.into_iter().zip(pieces) {
+        squares[n] = Some(piece);
+    }
+    Setup {rows: squares.chunks(cols).map(|row| row.to_vec()).collect()}
+}
+
+/// What a piece is worth to its owner, roughly.
+pub fn worth(piece: Piece) -> i32 {
+    match piece {
+        Piece::Flag       => 1000,
+        Piece::Marshall   => 80,
+        Piece::General    => 60,
+        Piece::Spy        => 50,
+        Piece::Colonel    => 40,
+        Piece::Major      => 30,
+        Piece::Miner      => 25,
+        Piece::Bomb       => 20,
+        Piece::Captain    => 20,
+        Piece::Lieutenant => 15,
+        Piece::Sergeant   => 10,
+        Piece::Scout      => 8,
+    }
+}
+
+/// How good attacking a piece of the rank is.
+fn battle(piece: Piece, rank: Piece) -> i32 {
+    match piece.attack(rank) {
+        BattleResult::Victory => worth(rank),
+        BattleResult::Draw    => worth(rank) - worth(piece),
+        BattleResult::Loss    => -worth(piece),
+    }
+}
+
+/// How good attacking an enemy piece of unknown rank looks, depending on
+/// whether it has moved and so can't be a bomb or the flag, and the odds of
+/// each rank it might be.
+fn gamble(piece: Piece, moved: bool, odds: &[(Piece, f64)]) -> i32 {
+    match piece {
+        // The spy only wins by attacking the marshall, so it waits until it
+        // knows where the marshall is.
+        Piece::Spy => -2 * worth(piece),
+        // Scouts are cheap, and whatever they strike is revealed.
+        Piece::Scout => 15 - worth(piece),
+        // Miners go after pieces that could be bombs.
+        Piece::Miner if !moved => 20,
+        // Anything else weighs up what it might run into.
+        _ => odds
+            .iter()
+            .map(|&(rank, chance)| chance * battle(piece, rank) as f64)
+            .sum::<f64>() as i32,
+    }
+}
+
+/// How many rows the move takes the piece towards the enemy's side.
+fn forward(colour: Colour, m: &Move) -> i32 {
+    let dy = m.to.y as i32 - m.from.y as i32;
+    match colour {
+        Colour::Red  => -dy,
+        Colour::Blue => dy,
+    }
+}
+
+/// The tiles next to the coordinate.
+fn neighbours(view: &View, c: Coord) -> Vec<Coord> {
+    [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .iter()
+        .filter_map(|&(x, y)| view.rules.geometry.offset(c, x, y))
+        .collect()
+}
+
+/// Whether the piece would be next to an enemy known to beat it, if it stood
+/// on the tile.
+fn threatened(view: &View, c: Coord, piece: Piece) -> bool {
+    neighbours(view, c).into_iter().any(|n| match view.tile_at(n) {
+        Seen::Enemy {rank: Some(rank), ..} =>
+            rank.attack(piece) == BattleResult::Victory,
+        _ => false,
+    })
+}
+
+/// How much the move does to defend the player's flag from enemy pieces
+/// within two tiles of it, by attacking them or closing in on them.
+fn defence(view: &View, m: &Move) -> i32 {
+    let distance = |a: Coord, b: Coord| {
+        (a.x as i32 - b.x as i32).abs() + (a.y as i32 - b.y as i32).abs()
+    };
+    let geometry = view.rules.geometry;
+    let flag = geometry
+        .coords()
+        .find(|&c| matches!(view.tile_at(c), Seen::Own {piece: Piece::Flag, ..}));
+    let flag = match flag {
+        Some(flag) => flag,
+        None => return 0,
+    };
+    let intruders = geometry
+        .coords()
+        .filter(|&c| matches!(view.tile_at(c), Seen::Enemy {..}))
+        .filter(|&c| distance(c, flag) <= 2)
+        .collect::<Vec<_>>();
+
+    let mut score = 0;
+    for c in intruders {
+        if m.to == c {
+            score += 30;
+        } else if distance(m.to, c) < distance(m.from, c) {
+            score += 10;
+        }
+    }
+    score
+}
@@ -1,6 +1,9 @@
 use std::io;
 use std::convert::From;
 
+use board::{Colour, Piece};
+use bot::Budget;
+
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 #[derive(Fail, Debug)]
@@ -9,6 +12,129 @@ pub enum Error {
     EarlyExit,
     #[fail(display = "{}", _0)]
     Io(#[cause] io::Error),
+    #[fail(display = "illegal move: {}", _0)]
+    IllegalMove(#[cause] IllegalMove),
+    #[fail(display = "{}", _0)]
+    Notation(#[cause] NotationError),
+    #[fail(display = "{}", _0)]
+    Record(#[cause] RecordError),
+    #[fail(display = "{}", _0)]
+    Gravon(#[cause] GravonError),
+    #[fail(display = "invalid setup: {}", _0)]
+    Setup(#[cause] SetupError),
+    #[fail(display = "{}", _0)]
+    Library(#[cause] LibraryError),
+    #[fail(display = "{}", _0)]
+    Bot(#[cause] BotError),
+}
+
+/// The reasons a move can be rejected by `Board::try_move`.
+#[derive(Fail, Copy, Clone, Debug, PartialEq)]
+pub enum IllegalMove {
+    #[fail(display = "coordinate is off the board")]
+    OutOfBounds,
+    #[fail(display = "there is no piece to move")]
+    EmptyTile,
+    #[fail(display = "terrain cannot be moved")]
+    Terrain,
+    #[fail(display = "{} cannot move", _0)]
+    Immobile(Piece),
+    #[fail(display = "cannot move onto an allied piece")]
+    AlliedPiece,
+    #[fail(display = "cannot move into terrain")]
+    IntoTerrain,
+    #[fail(display = "piece can only move to an adjacent tile")]
+    NotAdjacent,
+    #[fail(display = "scouts must move in an unbroken straight line")]
+    InvalidScoutPath,
+    #[fail(display = "scouts may only strike an adjacent piece")]
+    ScoutStrike,
+    #[fail(display = "it is {}'s turn to move", _0)]
+    OutOfTurn(Colour),
+    #[fail(display = "piece has moved between the same two squares too often")]
+    TwoSquareRule,
+    #[fail(display = "piece may not keep chasing into a repeated position")]
+    MoreSquareRule,
+}
+
+/// The reasons a move can't be read from game notation.
+#[derive(Fail, Clone, Debug, PartialEq)]
+pub enum NotationError {
+    #[fail(display = "invalid square \"{}\"", _0)]
+    Square(String),
+    #[fail(display = "invalid rank \"{}\"", _0)]
+    Rank(String),
+    #[fail(display = "expected \"-\" or \"x\" between squares in \"{}\"", _0)]
+    Separator(String),
+    #[fail(display = "ranks are only revealed by strikes, in \"{}\"", _0)]
+    RankWithoutStrike(String),
+}
+
+/// The reasons a setup can't be read from its written form.
+#[derive(Fail, Copy, Clone, Debug, PartialEq)]
+pub enum SetupError {
+    #[fail(display = "unknown piece \"{}\"", _0)]
+    Symbol(char),
+    #[fail(display = "{} squares given, which fits no board", _0)]
+    Length(usize),
+    #[fail(display = "{} pieces given, which is not the size of any army", _0)]
+    Count(usize),
+    #[fail(display = "{} of piece {} given, expected {}", _1, _0, _2)]
+    Army(Piece, usize, usize),
+}
+
+/// The reasons the setup library can't be read or changed.
+#[derive(Fail, Clone, Debug, PartialEq)]
+pub enum LibraryError {
+    #[fail(display = "invalid line {} in setup library: \"{}\"", _0, _1)]
+    InvalidLine(usize, String),
+    #[fail(display = "invalid setup name \"{}\"", _0)]
+    InvalidName(String),
+    #[fail(display = "\"{}\" is a built-in setup", _0)]
+    BuiltIn(String),
+}
+
+/// The reasons a game record can't be read or replayed.
+#[derive(Fail, Clone, Debug, PartialEq)]
+pub enum RecordError {
+    #[fail(display = "missing [{}] tag", _0)]
+    MissingTag(String),
+    #[fail(display = "invalid value for [{}] tag: \"{}\"", _0, _1)]
+    InvalidTag(String, String),
+    #[fail(display = "invalid line {}: \"{}\"", _0, _1)]
+    InvalidLine(usize, String),
+    #[fail(display = "move {}: {}", _0, _1)]
+    InvalidMove(usize, #[cause] NotationError),
+    #[fail(display = "move {} could not be replayed: {}", _0, _1)]
+    IllegalMove(usize, #[cause] IllegalMove),
+    #[fail(display = "move {} does not match the recorded battle", _0)]
+    Mismatch(usize),
+}
+
+/// The reasons a Gravon game can't be read or replayed.
+#[derive(Fail, Clone, Debug, PartialEq)]
+pub enum GravonError {
+    #[fail(display = "missing <{}> element", _0)]
+    MissingElement(String),
+    #[fail(display = "<{}> element is missing its {} attribute", _0, _1)]
+    MissingAttribute(String, String),
+    #[fail(display = "invalid value for {} attribute: \"{}\"", _0, _1)]
+    InvalidAttribute(String, String),
+    #[fail(display = "invalid field \"{}\"", _0)]
+    InvalidField(String),
+    #[fail(display = "{} has not set up exactly the pieces of an army", _0)]
+    IncompleteSetup(Colour),
+    #[fail(display = "move {}: {}", _0, _1)]
+    InvalidMove(usize, #[cause] NotationError),
+    #[fail(display = "move {} could not be replayed: {}", _0, _1)]
+    IllegalMove(usize, #[cause] IllegalMove),
+}
+
+/// The reasons a computer player can't be made by `bot::named`.
+#[derive(Fail, Copy, Clone, Debug, PartialEq)]
+pub enum BotError {
+    #[fail(display = "{} doesn't search within a budget of {}", _0, _1)]
+    Budget(&'static str, Budget),
 }
 
 impl From<io::Error> for Error {
@@ -16,3 +142,45 @@ impl From<io::Error> for Error {
         Error::Io(err)
     }
 }
+
+impl From<IllegalMove> for Error {
+    fn from(err: IllegalMove) -> Self {
+        Error::IllegalMove(err)
+    }
+}
+
+impl From<NotationError> for Error {
+    fn from(err: NotationError) -> Self {
+        Error::Notation(err)
+    }
+}
+
+impl From<RecordError> for Error {
+    fn from(err: RecordError) -> Self {
+        Error::Record(err)
+    }
+}
+
+impl From<GravonError> for Error {
+    fn from(err: GravonError) -> Self {
+        Error::Gravon(err)
+    }
+}
+
+impl From<SetupError> for Error {
+    fn from(err: SetupError) -> Self {
+        Error::Setup(err)
+    }
+}
+
+impl From<LibraryError> for Error {
+    fn from(err: LibraryError) -> Self {
+        Error::Library(err)
+    }
+}
+
+impl From<BotError> for Error {
+    fn from(err: BotError) -> Self {
+        Error::Bot(err)
+    }
+}
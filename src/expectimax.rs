@@ -0,0 +1,469 @@
+//! A computer player that searches a few moves ahead with expectimax.
+//!
+//! The player searches every line of play up to some number of moves deep,
+//! assuming that each side plays the move best for it, and judges the
+//! positions it ends up in with an `Evaluation`. Hidden enemy pieces stand in
+//! as the rank they most likely are until they fight, and each battle with one
+//! is worth the average of how it would turn out for every rank the piece
+//! might be, weighted by the odds of that rank.
+//!
+//! The search starts one move deep and goes one move deeper at a time until
+//! it reaches the depth of its budget or runs out of time, playing the best
+//! move of the deepest search it finished.
+
+use std::fmt;
+use std::rc::Rc;
+use std::time::Instant;
+
+use belief::Belief;
+use board::{BattleResult, Board, Colour, Coord, Move, Piece, Strike, Tile};
+use bot::{self, Budget};
+use error::{BotError, Error, Result};
+use player::Player;
+use random::{self, GameRng, Rng};
+use rules::Rules;
+use setup::Setup;
+use view::{Seen, View};
+
+/// What a won game is worth, more than any evaluation.
+const WIN: f64 = 1_000_000.0;
+
+/// The chance of each rank a hidden piece might be, shared between the
+/// positions searched.
+type Odds = Rc<[(Piece, f64)]>;
+
+/// A way of judging how good a position is, without searching any further.
+pub trait Evaluation {
+    /// How good the position is for the searching player, the higher the
+    /// better.
+    fn evaluate(&self, position: &Position) -> f64;
+}
+
+/// The worth of the searching player's pieces, less the enemy's.
+#[derive(Copy, Clone, Debug)]
+pub struct Material;
+
+impl Evaluation for Material {
+    fn evaluate(&self, position: &Position) -> f64 {
+        let mut total = 0.0;
+        for (c, colour) in position.pieces() {
+            let worth = position.expect(c, |p| match p {
+                Piece::Flag => 0.0,
+                _ => bot::worth(p) as f64,
+            });
+            total += if colour == position.colour() { worth } else { -worth };
+        }
+        total
+    }
+}
+
+/// How much of the enemy's material is next to something that could take it,
+/// less how much of the searching player's.
+#[derive(Copy, Clone, Debug)]
+pub struct Safety;
+
+impl Evaluation for Safety {
+    fn evaluate(&self, position: &Position) -> f64 {
+        let geometry = position.board().geometry();
+        let mut total = 0.0;
+        for (c, colour) in position.pieces() {
+            let danger = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .filter_map(|&(x, y)| geometry.offset(c, x, y))
+                .filter(|&n| matches!(position.board().tile_at(n), Tile::Piece(_, o) if o != colour))
+                .map(|n| position.chance_beats(n, c))
+                .fold(0.0, f64::max);
+            let worth = danger * position.expect(c, |p| bot::worth(p) as f64);
+            total += if colour == position.colour() { -worth } else { worth };
+        }
+        total
+    }
+}
+
+/// How close enemy pieces that could move are to the searching player's
+/// flag.
+#[derive(Copy, Clone, Debug)]
+pub struct FlagDefence;
+
+impl Evaluation for FlagDefence {
+    fn evaluate(&self, position: &Position) -> f64 {
+        let colour = position.colour();
+        let flag = position
+            .pieces()
+            .into_iter()
+            .find(|&(c, o)| o == colour && position.board().tile_at(c) == Tile::Piece(Piece::Flag, o));
+        let flag = match flag {
+            Some((flag, _)) => flag,
+            None => return 0.0,
+        };
+
+        let mut total = 0.0;
+        for (c, o) in position.pieces() {
+            let distance = (c.x as i32 - flag.x as i32).abs() + (c.y as i32 - flag.y as i32).abs();
+            if o != colour && distance <= 3 {
+                let mobile = position.expect(c, |p| match p {
+                    Piece::Bomb | Piece::Flag => 0.0,
+                    _ => 1.0,
+                });
+                total -= 10.0 * (4 - distance) as f64 * mobile;
+            }
+        }
+        total
+    }
+}
+
+/// How many more moves the searching player has than the enemy.
+#[derive(Copy, Clone, Debug)]
+pub struct Mobility;
+
+impl Evaluation for Mobility {
+    fn evaluate(&self, position: &Position) -> f64 {
+        let colour = position.colour();
+        let board = position.board();
+        board.find_all_moves(colour).len() as f64 - board.find_all_moves(colour.other()).len() as f64
+    }
+}
+
+/// A sum of evaluations, each scaled by its weight.
+pub struct Weighted {
+    pub terms: Vec<(f64, Box<dyn Evaluation>)>,
+}
+
+impl Evaluation for Weighted {
+    fn evaluate(&self, position: &Position) -> f64 {
+        self.terms
+            .iter()
+            .map(|&(weight, ref term)| weight * term.evaluate(position))
+            .sum()
+    }
+}
+
+impl fmt::Debug for Weighted {
+    /// Shows the weights, as evaluations needn't be `Debug` themselves.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let weights = self.terms.iter().map(|&(weight, _)| weight).collect::<Vec<_>>();
+        f.debug_struct("Weighted").field("weights", &weights).finish_non_exhaustive()
+    }
+}
+
+impl Default for Weighted {
+    /// Material first, then keeping pieces safe and the flag defended, then
+    /// room to move.
+    fn default() -> Self {
+        Weighted {
+            terms: vec![
+                (1.0, Box::new(Material)),
+                (0.5, Box::new(Safety)),
+                (1.0, Box::new(FlagDefence)),
+                (0.2, Box::new(Mobility)),
+            ],
+        }
+    }
+}
+
+/// A position in the search, as the searching player believes it to be.
+///
+/// The board holds the likeliest rank of each hidden enemy piece, which
+/// decides how it moves until it fights and is revealed.
+#[derive(Clone, Debug)]
+pub struct Position {
+    board:  Board,
+    /// The colour of the searching player.
+    colour: Colour,
+    /// The odds of each rank for the enemy pieces not yet revealed, on the
+    /// tiles where they stand.
+    hidden: Vec<Vec<Option<Odds>>>,
+    /// The colour that captured the other's flag, if either has.
+    winner: Option<Colour>,
+}
+
+impl Position {
+    /// The position as the player sees it, and believes the hidden pieces
+    /// to be. The belief must have been updated with the view.
+    pub fn new(view: &View, belief: &Belief) -> Self {
+        let geometry = view.rules.geometry;
+        let enemy = view.colour.other();
+        let mut board = Board::with_rules(view.rules);
+        let mut hidden = vec![vec![None; geometry.width as usize]; geometry.height as usize];
+        for c in geometry.coords() {
+            match view.tile_at(c) {
+                Seen::Own {piece, ..} => board.set_tile(c, Tile::Piece(piece, view.colour)),
+                Seen::Enemy {rank: Some(rank), ..} => board.set_tile(c, Tile::Piece(rank, enemy)),
+                Seen::Enemy {rank: None, ..} => {
+                    let odds = belief.odds(c);
+                    let likeliest = odds.first().map_or(Piece::Scout, |&(p, _)| p);
+                    board.set_tile(c, Tile::Piece(likeliest, enemy));
+                    hidden[c.y as usize][c.x as usize] = Some(odds.into());
+                }
+                _ => (),
+            }
+        }
+        board.set_to_move(view.to_move);
+        Position {board, colour: view.colour, hidden, winner: None}
+    }
+
+    pub fn board(&self) -> &Board {&self.board}
+
+    /// The colour of the searching player.
+    pub fn colour(&self) -> Colour {self.colour}
+
+    /// The odds of each rank for the piece on the tile, if it is a hidden
+    /// enemy piece.
+    pub fn hidden(&self, c: Coord) -> Option<&[(Piece, f64)]> {
+        self.hidden[c.y as usize][c.x as usize].as_ref().map(|odds| &odds[..])
+    }
+
+    /// Every piece on the board, and its colour.
+    pub fn pieces(&self) -> Vec<(Coord, Colour)> {
+        self.board
+            .geometry()
+            .coords()
+            .filter_map(|c| match self.board.tile_at(c) {
+                Tile::Piece(_, colour) => Some((c, colour)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The average of the function over every rank the piece on the tile
+    /// might be, weighted by their odds, or zero if there is no piece.
+    pub fn expect<F: Fn(Piece) -> f64>(&self, c: Coord, f: F) -> f64 {
+        match (self.board.tile_at(c), self.hidden(c)) {
+            (_, Some(odds)) => odds.iter().map(|&(p, chance)| chance * f(p)).sum(),
+            (Tile::Piece(piece, _), None) => f(piece),
+            _ => 0.0,
+        }
+    }
+
+    /// The chance that the piece on one tile would win by attacking the
+    /// piece on the other.
+    pub fn chance_beats(&self, attacker: Coord, defender: Coord) -> f64 {
+        self.expect(attacker, |a| match a {
+            Piece::Bomb | Piece::Flag => 0.0,
+            _ => self.expect(defender, |d| {
+                if a.attack(d) == BattleResult::Victory { 1.0 } else { 0.0 }
+            }),
+        })
+    }
+
+    /// Every move the side to move has.
+    pub fn moves(&self) -> Vec<Move> {
+        self.board.find_all_moves(self.board.to_move())
+    }
+
+    /// The positions the move might lead to, each with its chance. A battle
+    /// with a hidden piece leads to one for every rank it might be, and any
+    /// other move to just the one.
+    pub fn play(&self, m: Move) -> Vec<(Position, f64)> {
+        let battle = matches!(self.board.tile_at(m.to), Tile::Piece(..));
+        let fighter = [m.from, m.to].iter().cloned().find(|&c| self.hidden(c).is_some());
+        match fighter {
+            Some(c) if battle => {
+                let odds = self.hidden(c).unwrap();
+                odds.iter()
+                    .map(|&(rank, chance)| {
+                        let mut next = self.clone();
+                        next.hidden[c.y as usize][c.x as usize] = None;
+                        next.board.set_tile(c, Tile::Piece(rank, self.colour.other()));
+                        next.apply(m);
+                        (next, chance)
+                    })
+                    .collect()
+            }
+            _ => {
+                let mut next = self.clone();
+                let odds = next.hidden[m.from.y as usize][m.from.x as usize].take();
+                next.hidden[m.to.y as usize][m.to.x as usize] = odds;
+                next.apply(m);
+                vec![(next, 1.0)]
+            }
+        }
+    }
+
+    fn apply(&mut self, m: Move) {
+        let outcome = self.board.apply_move(m);
+        if let Some(Strike {defender: Piece::Flag, result: BattleResult::Victory, ..}) = outcome.strike {
+            self.winner = Some(outcome.colour);
+        }
+    }
+}
+
+/// A player that searches a few moves ahead, judging where each line of play
+/// ends up with an `Evaluation`.
+///
+/// It sets up the same way as the `Heuristic` player, and chooses at random
+/// between moves that look equally good.
+pub struct Expectimax {
+    budget:     Budget,
+    evaluation: Box<dyn Evaluation>,
+    rng:        GameRng,
+    /// What the player believes the enemy's hidden pieces are.
+    belief:     Option<Belief>,
+}
+
+impl Expectimax {
+    /// The budget the player searches within unless given another.
+    pub const BUDGET: Budget = Budget::Depth(3);
+
+    /// A player that searches within the budget on each turn with the
+    /// default `Weighted` evaluation, and whose choices are all drawn from
+    /// the seed. The budget is a depth or a time, as the search doesn't go
+    /// in rounds.
+    pub fn new(budget: Budget, seed: u64) -> ::std::result::Result<Self, BotError> {
+        Expectimax::with_evaluation(budget, seed, Box::new(Weighted::default()))
+    }
+
+    /// A player that judges positions with the evaluation.
+    pub fn with_evaluation(
+        budget: Budget,
+        seed: u64,
+        evaluation: Box<dyn Evaluation>
+    ) -> ::std::result::Result<Self, BotError> {
+        if let Budget::Iterations(_) = budget {
+            return Err(BotError::Budget("expectimax", budget))
+        }
+        Ok(Expectimax {
+            budget,
+            evaluation,
+            rng:    random::seeded(seed),
+            belief: None,
+        })
+    }
+}
+
+impl fmt::Debug for Expectimax {
+    /// Leaves out the evaluation, which needn't be `Debug` itself.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Expectimax")
+            .field("budget", &self.budget)
+            .field("rng", &self.rng)
+            .field("belief", &self.belief)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Player for Expectimax {
+    fn name(&self) -> String {"expectimax".to_string()}
+
+    fn setup(&mut self, colour: Colour, rules: Rules) -> Result<Setup> {
+        self.belief = Some(Belief::new(colour, rules));
+        Ok(bot::guarded_setup(rules, &mut self.rng))
+    }
+
+    fn choose_move(&mut self, view: &View) -> Result<Move> {
+        if view.moves().len() < 2 {
+            return view.moves().first().cloned().ok_or(Error::EarlyExit)
+        }
+        let belief = self.belief.get_or_insert_with(|| Belief::new(view.colour, view.rules));
+        belief.update(view);
+        let root = Position::new(view, belief);
+        let start = Instant::now();
+
+        let mut best = None;
+        for depth in 1 .. {
+            let deadline = match self.budget {
+                Budget::Depth(n) if depth > n => break,
+                Budget::Depth(_) => None,
+                // The first search always finishes, so that there is a move
+                // to play.
+                Budget::Time(_) if depth == 1 => None,
+                Budget::Time(time) => Some(start + time),
+                Budget::Iterations(_) => unreachable!("Expectimax::new turns down budgets of rounds"),
+            };
+            let search = Search {
+                evaluation: &*self.evaluation,
+                colour: view.colour,
+                deadline,
+            };
+            match search.best(&root, view.moves(), depth, &mut self.rng) {
+                Some((value, m)) => {
+                    best = Some(m);
+                    // Searching deeper can't find anything better than a
+                    // certain win.
+                    if value >= WIN {
+                        break
+                    }
+                }
+                None => break,
+            }
+        }
+        best.ok_or(Error::EarlyExit)
+    }
+
+    fn observe(&mut self, mv: Move, strike: Option<Strike>) {
+        if let Some(ref mut belief) = self.belief {
+            belief.observe(mv, strike);
+        }
+    }
+}
+
+/// One search to a fixed depth.
+struct Search<'a> {
+    evaluation: &'a dyn Evaluation,
+    /// The colour of the searching player.
+    colour:     Colour,
+    /// When to give up on the search, if ever.
+    deadline:   Option<Instant>,
+}
+
+impl<'a> Search<'a> {
+    /// The best of the moves for the searching player and its value, or
+    /// `None` if the search ran out of time. Moves that are as good as each
+    /// other are chosen between at random, so that the player doesn't keep
+    /// shuffling the same piece back and forth when nothing it can see
+    /// makes a difference.
+    fn best<R: Rng>(
+        &self,
+        position: &Position,
+        moves: &[Move],
+        depth: usize,
+        rng: &mut R
+    ) -> Option<(f64, Move)> {
+        let mut values = vec![];
+        for &m in moves {
+            values.push((self.after(position, m, depth)?, m));
+        }
+        let top = values.iter().map(|&(v, _)| v).fold(f64::MIN, f64::max);
+        let best = values.into_iter().filter(|&(v, _)| v >= top).collect::<Vec<_>>();
+        rng.choose(&best).cloned()
+    }
+
+    /// The value of the position for the searching player, looking the
+    /// number of moves ahead, or `None` if the search ran out of time.
+    fn value(&self, position: &Position, depth: usize) -> Option<f64> {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return None
+        }
+        // Winning sooner, or losing later, is better.
+        let win = WIN + depth as f64;
+        if let Some(winner) = position.winner {
+            return Some(if winner == self.colour { win } else { -win })
+        }
+        if depth == 0 {
+            return Some(self.evaluation.evaluate(position))
+        }
+
+        let player = position.board.to_move();
+        let mut best: Option<f64> = None;
+        for m in position.moves() {
+            let value = self.after(position, m, depth)?;
+            best = Some(match best {
+                Some(b) if player == self.colour => b.max(value),
+                Some(b) => b.min(value),
+                None => value,
+            });
+        }
+        // A player with no moves left loses.
+        Some(best.unwrap_or(if player == self.colour { -win } else { win }))
+    }
+
+    /// The value of playing the move, averaged over every rank a hidden
+    /// piece in a battle might be.
+    fn after(&self, position: &Position, m: Move, depth: usize) -> Option<f64> {
+        let mut total = 0.0;
+        for (next, chance) in position.play(m) {
+            total += chance * self.value(&next, depth - 1)?;
+        }
+        Some(total)
+    }
+}
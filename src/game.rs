@@ -1,25 +1,40 @@
+use std::fs;
 use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use termion::{clear, cursor};
 use termion::color as termcol;
 use termion::event::Key;
 use termion::input::TermRead;
 
-use board::{self, Board, Coord, Move, Tile};
-use error;
+use stratagem::belief::Belief;
+use stratagem::board::{self, Board, Colour, Coord, GameResult, Geometry, Move, Tile};
+use stratagem::error;
+use stratagem::library::Library;
+use stratagem::player::Player;
+use stratagem::random::GameRng;
+use stratagem::record::Record;
+use stratagem::rules::Rules;
+use stratagem::setup::Setup;
+use stratagem::view::{Seen, View};
 
-const BOARD_WIDTH: u16 = 32;
-const BOARD_HEIGHT: u16 = 12;
 const SLEEP_DURATION: u64 = 500;
 
-enum GameResult {
-    RedWin,
-    RedLoss,
-    Draw,
-    Ongoing
-}
-
 struct Game<R, W: Write> {
     board:       Board,
+    /// The computer or remote player for each colour, or `None` for a
+    /// player at the keyboard.
+    players:     [Option<Box<dyn Player>>; 2],
+    library:     Library,
+    /// The seed that `rng` and the computer players' seeds were drawn from,
+    /// kept for the game record.
+    seed:        u64,
+    rng:         GameRng,
+    /// What each colour believes the other's hidden pieces are.
+    beliefs:     [Belief; 2],
+    /// Whether the analysis overlay is shown, with the likeliest rank of each
+    /// hidden enemy piece.
+    overlay:     bool,
     cursor:      Coord,
     sel:         Option<Coord>,
     highlighted: Vec<Coord>,
@@ -40,40 +55,159 @@ impl<R, W: Write> Drop for Game<R, W> {
     }
 }
 
-pub fn init<R: Read, W: Write>(stdin: R, mut stdout: W, size: (u16, u16)) -> error::Result<()> {
+/// How a game in the terminal is set up.
+pub struct Options {
+    /// The computer or remote player for each colour, or `None` to have that
+    /// colour played at the keyboard.
+    pub red:     Option<Box<dyn Player>>,
+    pub blue:    Option<Box<dyn Player>>,
+    /// Where to save the record of the game once it ends, even if it was
+    /// abandoned.
+    pub archive: Option<PathBuf>,
+    /// The setups offered while drafting.
+    pub library: Library,
+    /// The seed for every random decision in the game.
+    pub seed:    u64,
+    /// The generator for the game's own random decisions, drawn from the
+    /// seed apart from those of the computer players.
+    pub rng:     GameRng,
+    pub rules:   Rules,
+}
+
+/// Plays a game in the terminal.
+pub fn init<R: Read, W: Write>(
+    stdin: R,
+    mut stdout: W,
+    size: (u16, u16),
+    options: Options
+) -> error::Result<GameResult> {
     write!(stdout, "{}", clear::All)?;
 
     let mut game = Game {
-        board:       Board::new(),
-        cursor:      Coord { x: 0, y: 9 },
+        board:       Board::with_rules(options.rules),
+        players:     [options.red, options.blue],
+        library:     options.library,
+        seed:        options.seed,
+        rng:         options.rng,
+        beliefs:     [
+            Belief::new(Colour::Red, options.rules),
+            Belief::new(Colour::Blue, options.rules),
+        ],
+        overlay:     false,
+        cursor:      Coord { x: 0, y: options.rules.geometry.height - 1 },
         sel:         None,
         highlighted: vec![],
-        size:        size,
+        size,
         stdin:       stdin.keys(),
-        stdout:      stdout,
+        stdout,
     };
 
-    game.setup(board::Colour::Red)?;
-    game.board.randomise(board::Colour::Blue);
-    // game.board.randomise(board::Colour::Red);
-    game.refresh(board::Colour::Red)?;
+    for &colour in &[Colour::Red, Colour::Blue] {
+        match game.players[colour.index()] {
+            Some(ref mut player) => {
+                let setup = player.setup(colour, game.board.rules())?;
+                game.board.place(colour, &setup);
+            }
+            None => game.setup(colour)?,
+        }
+    }
+    let viewer = game.viewer();
+    game.refresh(viewer)?;
 
-    game.run()
+    let result = game.run();
+    if let Some(dir) = options.archive {
+        game.archive(dir)?;
+    }
+    result
 }
 
 impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
 
-    /// The main game loop.
-    pub fn run(&mut self) -> error::Result<()> {
-        let mut player = board::Colour::Red;
+    /// The main game loop. Returns the result once the game has finished.
+    pub fn run(&mut self) -> error::Result<GameResult> {
+        loop {
+            let player = self.board.to_move();
+            let mv = match self.players[player.index()] {
+                Some(ref mut p) =>
+                    p.choose_move(&View::new(&self.board, player))?,
+                None => self.select_move(player)?,
+            };
+
+            // Show the opponent's piece in a battle before it is resolved,
+            // once the move is known to be legal.
+            self.board.check_move(&mv)?;
+            let viewer = self.viewer();
+            if let Tile::Piece(_, defender) = self.board.tile_at(mv.to) {
+                let hidden = if defender == viewer { mv.from } else { mv.to };
+                self.reveal(hidden, viewer)?;
+            }
+
+            let outcome = self.board.apply_move(mv);
+            for p in self.players.iter_mut().flatten() {
+                p.observe(outcome.mv, outcome.strike);
+            }
+            for belief in &mut self.beliefs {
+                belief.observe(outcome.mv, outcome.strike);
+            }
+
+            let viewer = self.viewer();
+            self.refresh(viewer)?;
+            let result = self.board.result();
+            if result != GameResult::Ongoing {
+                self.popup(&format!("{}", result))?;
+                return Ok(result)
+            }
+        }
+    }
+
+    /// Saves the record of the game so far in the directory `dir`, named
+    /// after the time it was saved.
+    fn archive(&self, dir: PathBuf) -> error::Result<()> {
+        let name = |colour: Colour| match self.players[colour.index()] {
+            Some(ref p) => p.name(),
+            None => "human".to_string(),
+        };
+        let mut record = match Record::from_board(
+            &self.board,
+            &name(Colour::Red),
+            &name(Colour::Blue),
+        ) {
+            Some(record) => record,
+            None => return Ok(()),
+        };
+        record.seed = Some(self.seed);
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{}.sgr", secs)), record.to_string())?;
+        Ok(())
+    }
+
+    /// The colour whose pieces are shown on the screen.
+    ///
+    /// This is the player at the keyboard, or whoever is to move if both
+    /// players are at the keyboard.
+    fn viewer(&self) -> Colour {
+        match self.players {
+            [None, Some(_)] => Colour::Red,
+            [Some(_), None] => Colour::Blue,
+            _ => self.board.to_move(),
+        }
+    }
 
+    /// Lets the player at the keyboard pick their next move.
+    fn select_move(&mut self, player: Colour) -> error::Result<Move> {
         macro_rules! mv {
-            ($x:expr, $y:expr) => (match self.cursor.offset($x, $y) {
+            ($x:expr, $y:expr) => (match self.board.geometry().offset(self.cursor, $x, $y) {
                 Some(c) => c,
                 None => self.cursor
             });
         }
 
+        self.refresh(player)?;
         while let Ok(k) = self.stdin.next().unwrap() {
             use termion::event::Key::*;
 
@@ -82,60 +216,22 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
                 Char('a') | Left  => self.cursor = mv!(-1, 0),
                 Char('s') | Down  => self.cursor = mv!(0, 1),
                 Char('d') | Right => self.cursor = mv!(1, 0),
-                Char('q') => break,
+                Char('q') => return Err(error::Error::EarlyExit),
+                Char('o') => {
+                    self.overlay = !self.overlay;
+                    if !self.overlay {
+                        self.draw_status("")?;
+                    }
+                }
                 Char(' ') | Char('\n') => {
                     match self.sel {
                         Some(selected) => {
-                            if self.highlighted.contains(&self.cursor) {
-
-                                // Conduct the move.
-                                match self.board.tile_at(self.cursor) {
-                                    // Show the piece attempting to be taken,
-                                    // then conduct the results.
-                                    Tile::Piece(p_enemy, _) => {
-                                        use board::BattleResult::*;
-
-                                        if let Tile::Piece(p_owned, _) =
-                                            self.board.tile_at(selected)
-                                        {
-                                            match p_owned.attack(p_enemy) {
-                                                // FIXME Probably some way
-                                                // of doing this without
-                                                // reallocation, even if
-                                                // it's just making `reveal`
-                                                // take a mutable.
-                                                Victory => {
-                                                    let cur = self.cursor;
-                                                    self.reveal(cur, player)?;
-                                                    self.board.apply_move(Move::new(selected, self.cursor));
-                                                },
-                                                Loss => {
-                                                    let cur = self.cursor;
-                                                    self.reveal(cur, player)?;
-                                                    self.board.set_tile(selected, Tile::Empty);
-                                                }
-                                                Draw => {
-                                                    let cur = self.cursor;
-                                                    self.reveal(cur, player)?;
-                                                    self.board.set_tile(selected, Tile::Empty);
-                                                    self.board.set_tile(self.cursor, Tile::Empty);
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    // Else just move.
-                                    _ => self.board
-                                        .apply_move(Move {
-                                            from: selected,
-                                            to: self.cursor
-                                        }),
-                                }
-                                player = player.other();
-                            }
-
+                            let chosen = self.highlighted.contains(&self.cursor);
                             self.sel = None;
                             self.highlighted.clear();
+                            if chosen {
+                                return Ok(Move::new(selected, self.cursor))
+                            }
                         }
                         None => {
                             if let Tile::Piece(_, col) = self.board.tile_at(self.cursor) {
@@ -161,89 +257,271 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
             self.refresh(player)?;
         }
 
-        Ok(())
+        Err(error::Error::EarlyExit)
     }
 
     /// Prompts the user to set up their side of the board.
     ///
-    /// By default, places pieces in order valued highest to lowest, with
-    /// stationary pieces first (i.e., flag, bombs, marshall, general, ...).
+    /// By default, places the pieces of the variant's army in order valued
+    /// highest to lowest, with stationary pieces first (i.e., flag, bombs,
+    /// marshall, general, ...). In armies too small to fill the setup area,
+    /// such as Barrage, the remaining squares are left empty.
+    /// Placed pieces can be picked back up or swapped with each other, any
+    /// change can be undone, and the remaining pieces can be placed at
+    /// random. A preset can be loaded from the library instead. Once every
+    /// piece is placed, the setup can be saved to the library, and play
+    /// starts when the user confirms it.
     fn setup(&mut self, player: board::Colour) -> error::Result<()> {
-        use board::Piece::*;
-        let mut to_place = vec![
-            Flag, Bomb, Bomb, Bomb, Bomb, Bomb, Bomb, Marshall, General,
-            Colonel, Colonel, Major, Major, Major, Captain, Captain, Captain,
-            Captain, Lieutenant, Lieutenant, Lieutenant, Lieutenant, Sergeant,
-            Sergeant, Sergeant, Sergeant, Miner, Miner, Miner, Miner, Miner,
-            Scout, Scout, Scout, Scout, Scout, Scout, Scout, Scout, Spy
-        ];
+        let army = self.board.rules().army;
+        let mut to_place = army.pieces().to_vec();
+        to_place.sort_by_key(|&p| (p != board::Piece::Flag, ::std::cmp::Reverse(p)));
 
         macro_rules! mv {
-            ($x:expr, $y:expr) => (match self.cursor.offset($x, $y) {
+            ($x:expr, $y:expr) => (match self.board.geometry().offset(self.cursor, $x, $y) {
                 Some(c) => c,
                 None => self.cursor
             });
         }
 
-        let offset = match player {
-            board::Colour::Red => 6,
-            board::Colour::Blue => 0,
-        };
-
-        for x in 0 .. 10 {
-            for y in 0 .. 4 {
-                let coord = Coord {x: x, y: y + offset};
-                self.highlighted.push(coord);
-            }
+        // The setup area and pieces left to place before each change, for
+        // undoing it.
+        let mut undo = vec![];
+        macro_rules! edit {
+            () => (undo.push((self.snapshot(player), to_place.clone())));
         }
 
-        self.draw_status(
-            format!("Next to place: {}", to_place[0])
-        )?;
-        self.refresh(player)?;
+        self.highlighted = setup_area(self.board.geometry(), player);
+        self.cursor = self.board.geometry().setup_coord(player, 0, 0);
+        self.sel = None;
+
+        // A message to show in place of the usual status, such as an error.
+        let mut message: Option<String> = None;
+        loop {
+            self.highlighted = setup_area(self.board.geometry(), player)
+                .into_iter()
+                .filter(|&c| self.board.tile_at(c) == Tile::Empty)
+                .collect();
+            let status = match (message.take(), self.sel) {
+                (Some(m), _) => m,
+                (None, Some(c)) =>
+                    format!("Moving {}: choose a tile", self.board.show(c, player)),
+                (None, None) if to_place.is_empty() =>
+                    "c: start, n: save, l: presets".to_string(),
+                (None, None) => format!(
+                    "Next to place: {}",
+                    to_place[0].symbol(self.board.rules().numbering)
+                ),
+            };
+            self.refresh(player)?;
+            self.draw_status(status)?;
+            self.stdout.flush()?;
 
-        while let Ok(k) = self.stdin.next().unwrap() {
+            let k = match self.stdin.next() {
+                Some(k) => k?,
+                None => return Err(error::Error::EarlyExit),
+            };
             use termion::event::Key::*;
 
+            let cur = self.cursor;
+            let own = match self.board.tile_at(cur) {
+                Tile::Piece(p, c) if c == player => Some(p),
+                _ => None,
+            };
             match k {
                 Char('w') | Up    => self.cursor = mv!(0, -1),
                 Char('a') | Left  => self.cursor = mv!(-1, 0),
                 Char('s') | Down  => self.cursor = mv!(0, 1),
                 Char('d') | Right => self.cursor = mv!(1, 0),
                 Char('q') => return Err(error::Error::EarlyExit),
-                Char('e') => {
+                Char('e') if !to_place.is_empty() => {
                     let last = to_place[0];
                     to_place.push(last);
                     to_place.remove(0);
                 }
-                Char(' ') | Char('\n') => {
-                    if self.highlighted.contains(&self.cursor) {
-                        let piece = to_place[0];
-                        let tile = Tile::Piece(piece, player);
-                        self.board.set_tile(self.cursor, tile);
-                        self.highlighted.remove_item(&self.cursor);
-                        to_place.remove(0);
+                Char(' ') | Char('\n') => match self.sel {
+                    // Move the selected piece, swapping it with any piece
+                    // already there.
+                    Some(from) if from == cur => self.sel = None,
+                    Some(from) if own.is_some()
+                        || self.highlighted.contains(&cur) =>
+                    {
+                        edit!();
+                        let moving = self.board.tile_at(from);
+                        let there = self.board.tile_at(cur);
+                        self.board.set_tile(from, there);
+                        self.board.set_tile(cur, moving);
+                        self.sel = None;
+                    }
+                    Some(_) => (),
+                    None if own.is_some() => self.sel = Some(cur),
+                    None if self.highlighted.contains(&cur)
+                        && !to_place.is_empty() =>
+                    {
+                        edit!();
+                        let piece = to_place.remove(0);
+                        self.board.set_tile(cur, Tile::Piece(piece, player));
+                    }
+                    None => (),
+                },
+                Esc => self.sel = None,
+                Char('x') | Backspace => {
+                    // Pick the piece back up, to be placed next.
+                    if let Some(piece) = own {
+                        edit!();
+                        self.board.set_tile(cur, Tile::Empty);
+                        to_place.insert(0, piece);
+                        self.sel = None;
+                    }
+                }
+                Char('u') => match undo.pop() {
+                    Some((tiles, pool)) => {
+                        self.restore(tiles);
+                        to_place = pool;
+                        self.sel = None;
+                    }
+                    None => message = Some("Nothing to undo".to_string()),
+                },
+                Char('r') if !to_place.is_empty() => {
+                    use stratagem::random::Rng;
+
+                    edit!();
+                    let mut empty = self.highlighted.clone();
+                    self.rng.shuffle(&mut empty);
+                    self.rng.shuffle(&mut to_place);
+                    for (&c, piece) in empty.iter().zip(to_place.drain(..)) {
+                        self.board.set_tile(c, Tile::Piece(piece, player));
+                    }
+                }
+                Char('l') => {
+                    let before = self.snapshot(player);
+                    let loaded = self.browse_presets(player)?;
+                    if loaded {
+                        undo.push((before, to_place.clone()));
+                        to_place.clear();
+                        self.sel = None;
                     }
                 }
+                Char('n') if to_place.is_empty() => {
+                    let setup = Setup::from_board(&self.board, player)
+                        .expect("every piece has been placed");
+                    if let Some(name) = self.prompt("Save as: ")? {
+                        message = Some(match self.library.save(&name, setup) {
+                            Ok(()) => format!("Saved \"{}\"", name.trim()),
+                            Err(e) => e.to_string(),
+                        });
+                    }
+                }
+                Char('c') if to_place.is_empty() => break,
                 _ => {}
             }
+        }
 
-            self.refresh(player)?;
-            if to_place.is_empty() {
+        self.highlighted.clear();
+        self.draw_status("")?;
+        Ok(())
+    }
+
+    /// The tiles of the player's setup area, as they are now.
+    fn snapshot(&self, player: Colour) -> Vec<(Coord, Tile)> {
+        setup_area(self.board.geometry(), player)
+            .into_iter()
+            .map(|c| (c, self.board.tile_at(c)))
+            .collect()
+    }
+
+    fn restore(&mut self, tiles: Vec<(Coord, Tile)>) {
+        for (c, tile) in tiles {
+            self.board.set_tile(c, tile);
+        }
+    }
+
+    /// Lets the user pick a preset for the variant's army and board from the
+    /// library, showing each one on their side of the board. Returns whether
+    /// one was chosen; if not, the board is left as it was.
+    fn browse_presets(&mut self, player: board::Colour) -> error::Result<bool> {
+        use termion::event::Key::*;
+
+        let saved = self.snapshot(player);
+        let highlighted = ::std::mem::take(&mut self.highlighted);
+        let rules = self.board.rules();
+        let mut i = 0;
+        let mut message: Option<String> = None;
+
+        loop {
+            let presets = self.library
+                .presets()
+                .iter()
+                .filter(|p| rules.allows(&p.setup))
+                .cloned()
+                .collect::<Vec<_>>();
+            let count = presets.len();
+            if count == 0 {
                 break
-            } else {
-                self.draw_status(
-                    format!("Next to place: {}", to_place[0])
-                )?;
-                self.stdout.flush()?;
+            }
+            let (name, setup, built_in) = {
+                let preset = &presets[i];
+                (preset.name.clone(), preset.setup.clone(), preset.built_in)
+            };
+            self.board.place(player, &setup);
+            self.refresh(player)?;
+            let status = message.take().unwrap_or_else(|| {
+                format!("{} ({}/{})", name, i + 1, count)
+            });
+            self.draw_status(status)?;
+            self.stdout.flush()?;
+
+            let k = match self.stdin.next() {
+                Some(k) => k?,
+                None => return Err(error::Error::EarlyExit),
+            };
+            match k {
+                Char('l') | Char('d') | Right => i = (i + 1) % count,
+                Char('a') | Left => i = (i + count - 1) % count,
+                Char(' ') | Char('\n') => return Ok(true),
+                Char('x') if built_in =>
+                    message = Some("Built-in setups can't be deleted".to_string()),
+                Char('x') => match self.library.delete(&name) {
+                    Ok(_) => i = i.min(count.saturating_sub(2)),
+                    Err(e) => message = Some(e.to_string()),
+                },
+                Char('q') => return Err(error::Error::EarlyExit),
+                Esc => break,
+                _ => {}
             }
         }
 
-        Ok(())
+        self.restore(saved);
+        self.highlighted = highlighted;
+        Ok(false)
+    }
+
+    /// Asks the user to type a line of text in the status line. Returns
+    /// `None` if they cancel with escape.
+    fn prompt(&mut self, question: &str) -> error::Result<Option<String>> {
+        let mut input = String::new();
+        loop {
+            self.draw_status(format!("{}{}", question, input))?;
+            self.stdout.flush()?;
+            match self.stdin.next() {
+                Some(Ok(Key::Char('\n'))) => return Ok(Some(input)),
+                Some(Ok(Key::Char(c))) => input.push(c),
+                Some(Ok(Key::Backspace)) => {
+                    input.pop();
+                }
+                Some(Ok(Key::Esc)) => return Ok(None),
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(_)) => {}
+                None => return Err(error::Error::EarlyExit),
+            }
+        }
     }
 
     fn refresh(&mut self, player: board::Colour) -> error::Result<()> {
         self.draw_board(player)?;
+        self.draw_known(player)?;
+        if self.overlay {
+            self.draw_overlay(player)?;
+        }
         self.highlight()?;
         self.draw_cursor(player)?;
         self.stdout.flush()?;
@@ -260,15 +538,22 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
     {
         let tl = self.top_left();
         write!(self.stdout,
-               "{}{}",
-               cursor::Goto(tl.0 + 1, tl.1 + 1 + BOARD_HEIGHT),
+               "{}{}{}",
+               cursor::Goto(tl.0 + 1, tl.1 + 1 + self.board_size().1),
+               clear::CurrentLine,
                status
         )?;
         Ok(())
     }
 
     fn top_left(&self) -> (u16, u16) {
-        ((self.size.0 - BOARD_WIDTH) / 2, (self.size.1 - BOARD_HEIGHT) / 2)
+        let (width, height) = self.board_size();
+        ((self.size.0 - width) / 2, (self.size.1 - height) / 2)
+    }
+
+    /// The width and height of the board on the screen, including its border.
+    fn board_size(&self) -> (u16, u16) {
+        board_size(self.board.geometry())
     }
 
     fn draw_board(&mut self, player: board::Colour) -> error::Result<()> {
@@ -294,7 +579,7 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
     fn draw_cursor(&mut self, player: board::Colour) -> error::Result<()> {
         let (x, y) = self.term_coords(self.cursor);
         let cursor =
-            format!("[{}]", self.board.tile_at(self.cursor).show(player));
+            format!("[{}]", self.board.show(self.cursor, player));
 
         write!(self.stdout, "{}", cursor::Goto(x - 1, y))?;
         if self.highlighted.contains(&self.cursor) {
@@ -313,7 +598,7 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
 
     fn highlight(&mut self) -> error::Result<()> {
         for t in &self.highlighted {
-            let (x, y) = self.term_coords(t.clone());
+            let (x, y) = self.term_coords(*t);
             write!(self.stdout, "{}{}   {}",
                    cursor::Goto(x - 1, y),
                    termcol::Bg(termcol::Red),
@@ -324,52 +609,119 @@ impl<R: Iterator<Item = Result<Key, io::Error>>, W: Write> Game<R, W> {
         Ok(())
     }
 
-    pub fn reveal(&mut self, c: Coord, player: board::Colour) -> error::Result<()> {
-        if let Tile::Piece(p, col) = self.board.tile_at(c) {
-            self.board.set_tile(c, Tile::Piece(p, col.other()));
-            self.refresh(player)?;
-            ::std::thread::sleep(::std::time::Duration::from_millis(SLEEP_DURATION));
+    /// Draws the ranks of enemy pieces that have been revealed in a
+    /// different colour, so they stand out from the player's own pieces.
+    fn draw_known(&mut self, player: board::Colour) -> error::Result<()> {
+        for c in self.board.geometry().coords() {
+            if let Tile::Piece(p, col) = self.board.tile_at(c) {
+                let revealed =
+                    self.board.info_at(c).is_some_and(|i| i.revealed);
+                if col != player && revealed {
+                    self.draw_revealed(c, p)?;
+                }
+            }
+        }
 
-            self.board.set_tile(c, Tile::Piece(p, col));
-            self.refresh(player)?;
+        Ok(())
+    }
+
+    /// Draws the likeliest rank of each hidden enemy piece, as the player
+    /// believes, in a different colour again, and the odds of every rank for
+    /// the piece under the cursor.
+    fn draw_overlay(&mut self, player: board::Colour) -> error::Result<()> {
+        let view = View::new(&self.board, player);
+        let belief = &mut self.beliefs[player.index()];
+        belief.update(&view);
+        let guesses = self.board
+            .geometry()
+            .coords()
+            .filter(|&c| matches!(view.tile_at(c), Seen::Enemy {rank: None, ..}))
+            .filter_map(|c| belief.likeliest(c).map(|p| (c, p)))
+            .collect::<Vec<_>>();
+        let odds = match view.tile_at(self.cursor) {
+            Seen::Enemy {rank: None, ..} => belief.odds(self.cursor),
+            _ => vec![],
+        };
+
+        let numbering = self.board.rules().numbering;
+        for (c, p) in guesses {
+            let (x, y) = self.term_coords(c);
+            write!(self.stdout, "{}{}{}{}",
+                   cursor::Goto(x, y),
+                   termcol::Fg(termcol::Cyan),
+                   p.symbol(numbering),
+                   termcol::Fg(termcol::Reset)
+            )?;
         }
+        let status = odds
+            .iter()
+            .take(4)
+            .map(|&(p, chance)| format!("{} {:.0}%", p.symbol(numbering), chance * 100.0))
+            .collect::<Vec<_>>()
+            .join("  ");
+        self.draw_status(status)
+    }
 
+    fn draw_revealed(&mut self, c: Coord, p: board::Piece) -> error::Result<()> {
+        let (x, y) = self.term_coords(c);
+        write!(self.stdout, "{}{}{}{}",
+               cursor::Goto(x, y),
+               termcol::Fg(termcol::Yellow),
+               p.symbol(self.board.rules().numbering),
+               termcol::Fg(termcol::Reset)
+        )?;
         Ok(())
     }
 
-    fn popup(&mut self, text: &str) -> error::Result<()> {
+    /// Briefly shows the piece on the tile to the player.
+    pub fn reveal(&mut self, c: Coord, player: board::Colour) -> error::Result<()> {
+        if let Tile::Piece(p, _) = self.board.tile_at(c) {
+            self.draw_revealed(c, p)?;
+            self.stdout.flush()?;
+            ::std::thread::sleep(::std::time::Duration::from_millis(SLEEP_DURATION));
+            self.refresh(player)?;
+        }
+
         Ok(())
     }
 
-    fn check_game_end(&mut self) -> GameResult {
-        let mut red = vec![];
-        let mut blue = vec![];
+    /// Draws a box containing the text over the middle of the board, and
+    /// waits for a key press to dismiss it.
+    fn popup(&mut self, text: &str) -> error::Result<()> {
+        let tl = self.top_left();
+        let (board_width, board_height) = self.board_size();
+        let width = text.chars().count() as u16 + 2;
+        let x = tl.0 + 1 + board_width.saturating_sub(width + 2) / 2;
+        let y = tl.1 + board_height / 2 - 1;
+        let bar = "─".repeat(width as usize);
 
-        for line in &self.board {
-            for tile in line {
-                if let &Tile::Piece(piece, col) = tile {
-                    match col {
-                        board::Colour::Red => red.push(piece),
-                        board::Colour::Blue => blue.push(piece),
-                    }
-                }
-            }
-        }
+        write!(self.stdout,
+               "{}┌{}┐{}│ {} │{}└{}┘",
+               cursor::Goto(x, y),
+               bar,
+               cursor::Goto(x, y + 1),
+               text,
+               cursor::Goto(x, y + 2),
+               bar
+        )?;
+        self.stdout.flush()?;
 
-        // Flags
-        if !red.contains(&board::Piece::Flag) {
-            return GameResult::RedLoss
-        } else if !blue.contains(&board::Piece::Flag) {
-            return GameResult::RedWin
+        match self.stdin.next() {
+            Some(Err(e)) => Err(e.into()),
+            _ => Ok(()),
         }
+    }
+}
 
-        // Only immobile units
-        if red.iter().fold(true, |acc, &p| acc && (p == board::Piece::Flag || p == board::Piece::Bomb)) {
-            return GameResult::RedLoss
-        } else if blue.iter().fold(true, |acc, &p| acc && (p == board::Piece::Flag || p == board::Piece::Bomb)) {
-            return GameResult::RedWin
-        }
+/// The width and height of a board on the screen, including its border.
+pub fn board_size(geometry: Geometry) -> (u16, u16) {
+    (geometry.width * 3 + 2, geometry.height + 2)
+}
 
-        GameResult::Ongoing
-    }
+/// The tiles a player sets up their pieces on.
+fn setup_area(geometry: Geometry, player: Colour) -> Vec<Coord> {
+    let (rows, cols) = (geometry.setup_rows as usize, geometry.width as usize);
+    (0 .. rows)
+        .flat_map(|r| (0 .. cols).map(move |c| geometry.setup_coord(player, r, c)))
+        .collect()
 }
@@ -0,0 +1,312 @@
+//! Import and export of games in the Gravon XML format, used by most public
+//! Stratego game archives.
+//!
+//! A Gravon game gives the starting position as a 100 character field
+//! string, followed by every move and the result:
+//!
+//! ```xml
+//! <stratego>
+//! <game>
+//! <field content="MBDLGFEDCE...AA__AA__AA...NOPQ"/>
+//! <move id="1" source="A4" target="A5"/>
+//! <move id="2" source="K7" target="K6"/>
+//! <result type="1" winner="0"/>
+//! </game>
+//! </stratego>
+//! ```
+//!
+//! The field string runs from square A1 to K1, then A2 to K2, and so on up to
+//! K10, with Red setting up on ranks 1 to 4. Squares use files A to K,
+//! leaving out I. Each character is a tile:
+//!
+//! - `A` is an empty tile and `_` a lake.
+//! - `B` to `M` are Red's flag, spy, scout, miner, sergeant, lieutenant,
+//!   captain, major, colonel, general, marshall and bomb.
+//! - `N` to `Y` are Blue's pieces, in the same order.
+//!
+//! Games of Barrage use the same format, with fewer pieces in the field, as
+//! do games on smaller boards, with one character for each of their squares.
+//!
+//! A winner of `0` is Red and `1` is Blue. A result type of `1` means the
+//! flag was captured and `2` that the loser had no moves left; other types,
+//! such as resignations, are kept as a winner without a reason.
+
+use std::fmt;
+
+use board::{Board, Colour, Coord, GameResult, Geometry, Move, Piece, Tile, WinReason};
+use error::GravonError;
+use notation::{self, Files};
+use record::Record;
+use rules::Rules;
+use setup::{Army, Setup};
+
+/// Each player's pieces, in the order of their field characters.
+const PIECES: [Piece; 12] = {
+    use board::Piece::*;
+    [
+        Flag, Spy, Scout, Miner, Sergeant, Lieutenant, Captain, Major,
+        Colonel, General, Marshall, Bomb
+    ]
+};
+
+/// A game read from or written to Gravon XML.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub red_setup:  Setup,
+    pub blue_setup: Setup,
+    pub moves:      Vec<Move>,
+    /// The winner of the game, if it finished with one.
+    pub winner:     Option<Colour>,
+    /// Why the game was won, if the winner is known and it was for a reason
+    /// recognised by the rules.
+    pub reason:     Option<WinReason>,
+}
+
+impl Game {
+    /// Reads the first game in a Gravon XML document.
+    pub fn read(xml: &str) -> Result<Self, GravonError> {
+        let field = elements(xml, "field")
+            .into_iter()
+            .next()
+            .ok_or_else(|| GravonError::MissingElement("field".to_string()))?;
+        let board = read_field(attribute(&field, "field", "content")?)?;
+        let geometry = board.geometry();
+        let setup = |colour| {
+            Setup::from_board(&board, colour)
+                .filter(|s| s.army().is_some())
+                .ok_or(GravonError::IncompleteSetup(colour))
+        };
+        let red_setup = setup(Colour::Red)?;
+        let blue_setup = setup(Colour::Blue)?;
+
+        let mut moves = vec![];
+        for (n, mv) in elements(xml, "move").iter().enumerate() {
+            let square = |name| {
+                notation::read_square(attribute(mv, "move", name)?, Files::AToK, geometry)
+                    .map_err(|e| GravonError::InvalidMove(n + 1, e))
+            };
+            moves.push(Move::new(square("source")?, square("target")?));
+        }
+
+        let (winner, reason) = match elements(xml, "result").first() {
+            None => (None, None),
+            Some(result) => {
+                let winner = match attribute(result, "result", "winner")? {
+                    "0" => Colour::Red,
+                    "1" => Colour::Blue,
+                    w => return Err(GravonError::InvalidAttribute(
+                        "winner".to_string(),
+                        w.to_string(),
+                    )),
+                };
+                let reason = match attribute(result, "result", "type") {
+                    Ok("1") => Some(WinReason::FlagCaptured),
+                    Ok("2") => Some(WinReason::NoMoves),
+                    _ => None,
+                };
+                (Some(winner), reason)
+            }
+        };
+
+        Ok(Game { red_setup, blue_setup, moves, winner, reason })
+    }
+
+    /// Sets up a new board and replays the first `ply` moves of the game
+    /// onto it, checking that each move is legal. Games are played on the
+    /// board the setups are for, with the Barrage army if they place it, and
+    /// otherwise by the classic rules.
+    pub fn board_at(&self, ply: usize) -> Result<Board, GravonError> {
+        let mut board = Board::with_rules(self.rules());
+        board.place(Colour::Red, &self.red_setup);
+        board.place(Colour::Blue, &self.blue_setup);
+        for (n, mv) in self.moves.iter().take(ply).enumerate() {
+            board
+                .try_move(*mv)
+                .map_err(|e| GravonError::IllegalMove(n + 1, e))?;
+        }
+        Ok(board)
+    }
+
+    /// The rules for the army and board of the setups.
+    fn rules(&self) -> Rules {
+        Rules {
+            army:     self.red_setup.army().unwrap_or(Army::Classic),
+            geometry: self.red_setup.geometry().unwrap_or(Geometry::CLASSIC),
+            ..Rules::default()
+        }
+    }
+}
+
+impl From<&Record> for Game {
+    /// Exports one of our own games. Gravon has no way to record a draw, so
+    /// drawn games are written without a result.
+    fn from(record: &Record) -> Self {
+        let (winner, reason) = match record.result {
+            GameResult::Win(c, r) => (Some(c), Some(r)),
+            _ => (None, None),
+        };
+        Game {
+            red_setup:  record.red_setup.clone(),
+            blue_setup: record.blue_setup.clone(),
+            moves:      record.moves.iter().map(|n| n.mv).collect(),
+            winner,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let geometry = self.rules().geometry;
+        let mut board = Board::with_rules(self.rules());
+        board.place(Colour::Red, &self.red_setup);
+        board.place(Colour::Blue, &self.blue_setup);
+
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(f, "<stratego>")?;
+        writeln!(f, "<game>")?;
+        writeln!(f, "<field content=\"{}\"/>", write_field(&board))?;
+        for (n, mv) in self.moves.iter().enumerate() {
+            writeln!(
+                f,
+                "<move id=\"{}\" source=\"{}\" target=\"{}\"/>",
+                n + 1,
+                notation::write_square(mv.from, Files::AToK, geometry),
+                notation::write_square(mv.to, Files::AToK, geometry),
+            )?;
+        }
+        if let Some(winner) = self.winner {
+            let kind = match self.reason {
+                Some(WinReason::FlagCaptured) => 1,
+                Some(WinReason::NoMoves) => 2,
+                None => 0,
+            };
+            writeln!(
+                f,
+                "<result type=\"{}\" winner=\"{}\"/>",
+                kind,
+                winner.index()
+            )?;
+        }
+        writeln!(f, "</game>")?;
+        writeln!(f, "</stratego>")
+    }
+}
+
+/// Reads a field string onto a new board, of the size the string has a
+/// character for each square of.
+///
+/// Lakes must be where the board has them, but either player's pieces may be
+/// anywhere else.
+pub fn read_field(s: &str) -> Result<Board, GravonError> {
+    let err = || GravonError::InvalidField(s.to_string());
+    let tiles = s.chars().collect::<Vec<_>>();
+    let geometry = Geometry::ALL
+        .iter()
+        .cloned()
+        .find(|g| g.width as usize * g.height as usize == tiles.len())
+        .ok_or_else(err)?;
+
+    let mut board = Board::with_rules(Rules { geometry, ..Rules::default() });
+    for (n, &ch) in tiles.iter().enumerate() {
+        let c = field_coord(n, geometry);
+        let tile = match ch {
+            'A' => Tile::Empty,
+            '_' => Tile::Terrain,
+            'B' ..= 'M' => Tile::Piece(PIECES[ch as usize - 'B' as usize], Colour::Red),
+            'N' ..= 'Y' => Tile::Piece(PIECES[ch as usize - 'N' as usize], Colour::Blue),
+            _ => return Err(err()),
+        };
+        if (tile == Tile::Terrain) != (board.tile_at(c) == Tile::Terrain) {
+            return Err(err())
+        }
+        if tile != Tile::Terrain {
+            board.set_tile(c, tile);
+        }
+    }
+    Ok(board)
+}
+
+/// Writes the board's current position as a field string.
+pub fn write_field(board: &Board) -> String {
+    let geometry = board.geometry();
+    (0 .. geometry.width as usize * geometry.height as usize)
+        .map(|n| match board.tile_at(field_coord(n, geometry)) {
+            Tile::Empty => 'A',
+            Tile::Terrain => '_',
+            Tile::Piece(p, colour) => {
+                let first = match colour {
+                    Colour::Red  => b'B',
+                    Colour::Blue => b'N',
+                };
+                let index = PIECES.iter().position(|&q| q == p).unwrap();
+                (first + index as u8) as char
+            }
+        })
+        .collect()
+}
+
+/// The board coordinate of the `n`th character of a field string.
+fn field_coord(n: usize, geometry: Geometry) -> Coord {
+    let width = geometry.width as usize;
+    Coord {x: (n % width) as u16, y: geometry.height - 1 - (n / width) as u16}
+}
+
+/// The attributes of every element with the given name, in document order.
+///
+/// This is only as much of XML as Gravon files use: no entities, comments or
+/// CDATA.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<Vec<(&'a str, &'a str)>> {
+    let open = format!("<{}", name);
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open[..]) {
+        rest = &rest[start + open.len() ..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[.. end];
+        rest = &rest[end ..];
+        // Skip elements whose names only start with the one wanted.
+        match tag.chars().next() {
+            Some(c) if c.is_whitespace() || c == '/' => (),
+            None => (),
+            _ => continue,
+        }
+        found.push(attributes(tag.trim_end_matches('/')));
+    }
+    found
+}
+
+/// Reads attributes such as `id="1" source='A4'`.
+fn attributes(mut s: &str) -> Vec<(&str, &str)> {
+    let mut attrs = vec![];
+    while let Some(eq) = s.find('=') {
+        let name = s[.. eq].trim();
+        let value = s[eq + 1 ..].trim_start();
+        let quote = match value.chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => break,
+        };
+        let value = &value[1 ..];
+        let end = match value.find(quote) {
+            Some(end) => end,
+            None => break,
+        };
+        attrs.push((name, &value[.. end]));
+        s = &value[end + 1 ..];
+    }
+    attrs
+}
+
+fn attribute<'a>(
+    attrs: &[(&str, &'a str)],
+    element: &str,
+    name: &str
+) -> Result<&'a str, GravonError> {
+    attrs
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, v)| v)
+        .ok_or_else(|| {
+            GravonError::MissingAttribute(element.to_string(), name.to_string())
+        })
+}
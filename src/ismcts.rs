@@ -0,0 +1,264 @@
+//! A computer player that searches ahead with information set Monte Carlo
+//! tree search.
+//!
+//! The player can't see the ranks of the enemy's hidden pieces, so each round
+//! of the search starts by guessing them, drawing from its belief of what
+//! they might be. It then plays down a tree of moves shared by every guess,
+//! favouring moves that have done well so far but trying the others now and
+//! then, adds one new move to the tree, and finishes with a short run of
+//! random moves. How that run ends is counted towards every move played on
+//! the way down. Moves that can't be played with a round's guess are passed
+//! over for that round.
+//!
+//! The search only uses the board's own move generation and battles, on the
+//! CPU of the machine it runs on.
+
+use std::time::Instant;
+
+use belief::Belief;
+use board::{Board, Colour, GameResult, Move, Piece, Strike, Tile};
+use bot::{self, Budget};
+use error::{BotError, Error, Result};
+use player::Player;
+use random::{self, GameRng, Rng};
+use rules::Rules;
+use setup::Setup;
+use view::{Seen, View};
+
+/// How many random moves finish each round of the search. Longer runs of
+/// random moves say less about the move being searched.
+const PLAYOUT: usize = 6;
+
+/// How much the search favours trying moves it has tried less, over playing
+/// moves that have done well.
+const EXPLORATION: f64 = 0.7;
+
+/// A lead in material worth this much makes a playout about three quarters
+/// as good as a win.
+const LEAD: f64 = 50.0;
+
+/// A move in the search tree, and how well it has done.
+#[derive(Debug)]
+struct Node {
+    /// The move leading to the node, or `None` at the root.
+    mv:        Option<Move>,
+    /// The colour that played the move.
+    colour:    Colour,
+    children:  Vec<usize>,
+    /// The number of rounds that played the move.
+    visits:    f64,
+    /// The total score of those rounds, for the colour that played it.
+    score:     f64,
+    /// The number of rounds in which the move could have been played.
+    available: f64,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, colour: Colour) -> Self {
+        Node {
+            mv,
+            colour,
+            children:  vec![],
+            visits:    0.0,
+            score:     0.0,
+            available: 1.0,
+        }
+    }
+
+    /// How promising the move looks for another round.
+    fn priority(&self) -> f64 {
+        self.score / self.visits
+            + EXPLORATION * (self.available.ln() / self.visits).sqrt()
+    }
+}
+
+/// A player that plays the move that did best in a search of the moves ahead,
+/// over many guesses at the enemy's hidden pieces.
+///
+/// It sets up the same way as the `Heuristic` player.
+#[derive(Debug)]
+pub struct Ismcts {
+    budget: Budget,
+    rng:    GameRng,
+    /// What the player believes the enemy's hidden pieces are.
+    belief: Option<Belief>,
+}
+
+impl Ismcts {
+    /// The budget the player searches within unless given another.
+    pub const BUDGET: Budget = Budget::Iterations(2000);
+
+    /// A player that searches within the budget on each turn, and whose
+    /// choices are all drawn from the seed. The budget is a number of rounds
+    /// or a time, as the search doesn't go to a set depth.
+    pub fn new(budget: Budget, seed: u64) -> ::std::result::Result<Self, BotError> {
+        if let Budget::Depth(_) = budget {
+            return Err(BotError::Budget("ismcts", budget))
+        }
+        Ok(Ismcts { budget, rng: random::seeded(seed), belief: None })
+    }
+}
+
+impl Player for Ismcts {
+    fn name(&self) -> String {"ismcts".to_string()}
+
+    fn setup(&mut self, colour: Colour, rules: Rules) -> Result<Setup> {
+        self.belief = Some(Belief::new(colour, rules));
+        Ok(bot::guarded_setup(rules, &mut self.rng))
+    }
+
+    fn choose_move(&mut self, view: &View) -> Result<Move> {
+        if view.moves().len() < 2 {
+            return view.moves().first().cloned().ok_or(Error::EarlyExit)
+        }
+        let belief = self.belief.get_or_insert_with(|| Belief::new(view.colour, view.rules));
+        belief.update(view);
+        search(view, belief, self.budget, &mut self.rng).ok_or(Error::EarlyExit)
+    }
+
+    fn observe(&mut self, mv: Move, strike: Option<Strike>) {
+        if let Some(ref mut belief) = self.belief {
+            belief.observe(mv, strike);
+        }
+    }
+}
+
+/// Searches for the best move within the budget, or `None` if there are no
+/// legal moves.
+fn search<R: Rng>(view: &View, belief: &Belief, budget: Budget, rng: &mut R) -> Option<Move> {
+    let mut tree = vec![Node::new(None, view.colour.other())];
+    let start = Instant::now();
+    let mut rounds = 0;
+    while match budget {
+        Budget::Iterations(n) => rounds < n,
+        Budget::Depth(_) => unreachable!("Ismcts::new turns down depth budgets"),
+        Budget::Time(time) => start.elapsed() < time,
+    } {
+        rounds += 1;
+        let mut board = guess(view, belief, rng);
+        let mut path = vec![0];
+        let mut node = 0;
+
+        // Follow the most promising moves until one that hasn't been tried
+        // with this guess comes up.
+        while board.result() == GameResult::Ongoing {
+            // The first move is taken from the view, which knows the rules on
+            // repetition from the whole game.
+            let moves = if node == 0 {
+                view.moves().to_vec()
+            } else {
+                board.find_all_moves(board.to_move())
+            };
+            let tried = tree[node]
+                .children
+                .iter()
+                .cloned()
+                .filter(|&c| moves.iter().any(|&m| tree[c].mv == Some(m)))
+                .collect::<Vec<_>>();
+            for &child in &tried {
+                tree[child].available += 1.0;
+            }
+            let untried = moves
+                .into_iter()
+                .filter(|&m| !tree[node].children.iter().any(|&c| tree[c].mv == Some(m)))
+                .collect::<Vec<_>>();
+
+            let next = match rng.choose(&untried) {
+                Some(&m) => {
+                    tree.push(Node::new(Some(m), board.to_move()));
+                    let child = tree.len() - 1;
+                    tree[node].children.push(child);
+                    child
+                }
+                None => match tried.into_iter().max_by(|&a, &b| {
+                    tree[a].priority().partial_cmp(&tree[b].priority()).unwrap()
+                }) {
+                    Some(child) => child,
+                    None => break,
+                },
+            };
+            board.apply_move(tree[next].mv.unwrap());
+            path.push(next);
+            node = next;
+            if !untried.is_empty() {
+                break
+            }
+        }
+
+        let score = match board.result() {
+            GameResult::Ongoing => playout(&mut board, view.colour, rng),
+            GameResult::Win(winner, _) if winner == view.colour => 1.0,
+            GameResult::Win(..) => 0.0,
+            GameResult::Draw(_) => 0.5,
+        };
+        for &n in &path {
+            let node = &mut tree[n];
+            node.visits += 1.0;
+            node.score += if node.colour == view.colour { score } else { 1.0 - score };
+        }
+    }
+
+    tree[0]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| tree[a].visits.partial_cmp(&tree[b].visits).unwrap())
+        .and_then(|&child| tree[child].mv)
+        .or_else(|| view.moves().first().cloned())
+}
+
+/// A board with the position the player sees, and a guess at the rank of
+/// each hidden enemy piece.
+fn guess<R: Rng>(view: &View, belief: &Belief, rng: &mut R) -> Board {
+    let enemy = view.colour.other();
+    let mut board = Board::with_rules(view.rules);
+    for c in view.rules.geometry.coords() {
+        match view.tile_at(c) {
+            Seen::Own {piece, ..} => board.set_tile(c, Tile::Piece(piece, view.colour)),
+            Seen::Enemy {rank: Some(rank), ..} => board.set_tile(c, Tile::Piece(rank, enemy)),
+            _ => (),
+        }
+    }
+    for (c, piece) in belief.sample(view, rng) {
+        board.set_tile(c, Tile::Piece(piece, enemy));
+    }
+    board.set_to_move(view.to_move);
+    board
+}
+
+/// Plays random moves for a while, and scores the result for the colour
+/// between zero for a loss and one for a win.
+///
+/// Checks for the end of the game itself rather than with `Board::result`,
+/// which also looks for repeated positions and is too slow to run after
+/// every move. A playout that repeats itself is scored on material.
+fn playout<R: Rng>(board: &mut Board, colour: Colour, rng: &mut R) -> f64 {
+    for _ in 0 .. PLAYOUT {
+        let player = board.to_move();
+        let moves = board.find_all_moves(player);
+        let m = match rng.choose(&moves) {
+            Some(&m) => m,
+            None => return if player == colour { 0.0 } else { 1.0 },
+        };
+        let outcome = board.apply_move(m);
+        if outcome.strike.is_some_and(|s| s.defender == Piece::Flag) {
+            return if player == colour { 1.0 } else { 0.0 }
+        }
+    }
+    1.0 / (1.0 + (-material(board, colour) / LEAD).exp())
+}
+
+/// How much more the colour's pieces are worth than the enemy's.
+fn material(board: &Board, colour: Colour) -> f64 {
+    let mut total = 0;
+    for row in board {
+        for tile in row {
+            match *tile {
+                Tile::Piece(Piece::Flag, _) => (),
+                Tile::Piece(piece, c) if c == colour => total += bot::worth(piece),
+                Tile::Piece(piece, _) => total -= bot::worth(piece),
+                _ => (),
+            }
+        }
+    }
+    total as f64
+}
@@ -0,0 +1,26 @@
+//! Stratego, but in Rust.
+//!
+//! The board model, rules and notation of the game, independent of any
+//! front-end.
+
+#[macro_use] extern crate failure;
+extern crate rand;
+
+pub mod belief;
+pub mod board;
+pub mod bot;
+pub mod error;
+pub mod expectimax;
+pub mod gravon;
+pub mod ismcts;
+pub mod library;
+pub mod notation;
+pub mod player;
+pub mod random;
+pub mod record;
+pub mod rules;
+pub mod setup;
+pub mod view;
+
+#[cfg(test)]
+mod tests;
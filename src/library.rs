@@ -0,0 +1,155 @@
+//! A library of named setups, so players can reuse their favourites.
+//!
+//! Saved setups, for any army and board, are kept in a text file, one per
+//! line, as the setup followed by its name:
+//!
+//! ```text
+//! 5879896958949FS7458968BB2B7B3973B4561B96 my favourite
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored. A few built-in setups
+//! are always available, and can't be replaced or deleted.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use error::{LibraryError, Result};
+use setup::{Setup, DEFAULT_SETUP};
+
+/// The setups every library starts with, by name.
+const BUILT_IN: [(&str, &str); 5] = [
+    ("default",      DEFAULT_SETUP),
+    ("corner flag",  "FB856878B5B76493596484B29S13B99679B59879"),
+    ("centre flag",  "586BFB78587B46B463959391S29B489678B59979"),
+    ("barrage",      "...BF8........2......S........9..1....9."),
+    ("barrage duel", ".8BF.9....2..S..9...1..."),
+];
+
+/// A named setup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name:     String,
+    pub setup:    Setup,
+    /// Whether the preset ships with the game, rather than being saved by a
+    /// player.
+    pub built_in: bool,
+}
+
+/// The built-in presets, along with any saved in the library's file.
+#[derive(Clone, Debug)]
+pub struct Library {
+    path:    Option<PathBuf>,
+    presets: Vec<Preset>,
+}
+
+impl Library {
+    /// A library of only the built-in presets, which can't be saved to.
+    pub fn built_in() -> Self {
+        let presets = BUILT_IN
+            .iter()
+            .map(|&(name, setup)| Preset {
+                name:     name.to_string(),
+                setup:    Setup::read(setup).unwrap(),
+                built_in: true,
+            })
+            .collect();
+        Library { path: None, presets }
+    }
+
+    /// Opens the library saved at `path`. The file is created when a preset
+    /// is first saved, if it doesn't exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut library = Library::built_in();
+        let path = path.as_ref().to_path_buf();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        for (n, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let invalid = || LibraryError::InvalidLine(n + 1, line.to_string());
+            let (setup, name) = match line.find(char::is_whitespace) {
+                Some(i) => (&line[.. i], line[i ..].trim()),
+                None => return Err(invalid().into()),
+            };
+            let setup = Setup::read(setup).map_err(|_| invalid())?;
+            library.insert(name, setup)?;
+        }
+
+        library.path = Some(path);
+        Ok(library)
+    }
+
+    /// Every preset, built-in ones first.
+    pub fn presets(&self) -> &[Preset] {&self.presets}
+
+    /// Saves the setup under the name, replacing any saved preset with the
+    /// same name.
+    pub fn save(&mut self, name: &str, setup: Setup) -> Result<()> {
+        self.insert(name, setup)?;
+        self.write()
+    }
+
+    /// Deletes the saved preset with the name. Returns whether there was one.
+    pub fn delete(&mut self, name: &str) -> Result<bool> {
+        match self.presets.iter().position(|p| p.name == name) {
+            Some(i) if self.presets[i].built_in =>
+                Err(LibraryError::BuiltIn(name.to_string()).into()),
+            Some(i) => {
+                self.presets.remove(i);
+                self.write()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn insert(&mut self, name: &str, setup: Setup) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || name.contains('\n') {
+            return Err(LibraryError::InvalidName(name.to_string()).into())
+        }
+        let preset = Preset {
+            name:     name.to_string(),
+            setup,
+            built_in: false,
+        };
+        match self.presets.iter().position(|p| p.name == name) {
+            Some(i) if self.presets[i].built_in =>
+                Err(LibraryError::BuiltIn(name.to_string()).into()),
+            Some(i) => {
+                self.presets[i] = preset;
+                Ok(())
+            }
+            None => {
+                self.presets.push(preset);
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes the saved presets to the library's file, if it has one.
+    fn write(&self) -> Result<()> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = self
+            .presets
+            .iter()
+            .filter(|p| !p.built_in)
+            .map(|p| format!("{} {}\n", p.setup, p.name))
+            .collect::<String>();
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
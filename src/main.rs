@@ -1,39 +1,166 @@
-#![feature(vec_remove_item)]
-#[macro_use] extern crate failure;
-extern crate rand;
+extern crate stratagem;
 extern crate termion;
 
-mod board;
-mod error;
-mod tests;
 mod game;
 
-use std::io::{self, Write};
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use stratagem::board::Geometry;
+use stratagem::bot::{self, Budget};
+use stratagem::error;
+use stratagem::library::Library;
+use stratagem::player::Player;
+use stratagem::random::{self, Rng};
+use stratagem::rules::{Numbering, Rules};
+use stratagem::setup::Army;
 use termion::raw::IntoRawMode;
 
+const USAGE: &str = "\
+Usage: stratagem [options]
+
+Options:
+    --record <dir>        save a record of the game in <dir>
+    --setups <file>       keep saved setups in <file>
+    --seed <number>       seed every random choice in the game
+    --red <player>        who plays Red: human (the default), random, the
+                          computer on easy, medium or hard, ismcts, or
+                          expectimax
+    --blue <player>       who plays Blue, as for Red
+    --budget <n>[ply|ms]  how many rounds ismcts searches for each move (2000
+                          by default), how many moves ahead expectimax looks
+                          with ply (3ply by default), or how many
+                          milliseconds either thinks with ms, which can't be
+                          replayed from the seed
+    --barrage             play Barrage, with 8 pieces each
+    --duel                play on the 8x8 duel board
+    --modern-ranks        number ranks from the spy at 1 to the marshall at 10
+    --no-scout-strikes    only let scouts strike adjacent pieces
+    --defender-advances   move winning defenders onto the attacker's tile";
+
+fn usage() -> ! {
+    println!("{}", USAGE);
+    ::std::process::exit(1)
+}
+
+/// The computer player with the name, or `None` for a human at the
+/// keyboard. Exits with the usage if there is no such player, or it can't
+/// search within the budget.
+fn player(name: &str, seed: u64, budget: Option<Budget>) -> Option<Box<dyn Player>> {
+    match name {
+        "human" => None,
+        _ => match bot::named(name, seed, budget) {
+            Ok(Some(player)) => Some(player),
+            Ok(None) => usage(),
+            Err(e) => {
+                println!("{}", e);
+                usage()
+            }
+        },
+    }
+}
+
 fn main() {
+    let mut archive = None;
+    let (mut red, mut blue) = ("human".to_string(), "human".to_string());
+    let mut seed = None;
+    let mut budget = None;
+    let mut rules = Rules::default();
+    // Saved setups are kept in the home directory unless given elsewhere.
+    let mut setups = env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".stratagem").join("setups"));
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--record" => match args.next() {
+                Some(dir) => archive = Some(PathBuf::from(dir)),
+                None => usage(),
+            },
+            "--seed" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(n) => seed = Some(n),
+                None => usage(),
+            },
+            "--red" => match args.next() {
+                Some(name) => red = name,
+                None => usage(),
+            },
+            "--blue" => match args.next() {
+                Some(name) => blue = name,
+                None => usage(),
+            },
+            "--budget" => match args.next().and_then(|s| Budget::from(&s)) {
+                Some(b) => budget = Some(b),
+                None => usage(),
+            },
+            "--setups" => match args.next() {
+                Some(file) => setups = Some(PathBuf::from(file)),
+                None => usage(),
+            },
+            "--barrage" => rules.army = Army::Barrage,
+            "--duel" => rules.geometry = Geometry::DUEL,
+            "--modern-ranks" => rules.numbering = Numbering::Modern,
+            "--no-scout-strikes" => rules.scout_strikes = false,
+            "--defender-advances" => rules.defender_advances = true,
+            _ => usage(),
+        }
+    }
+
+    if !rules.army_fits() {
+        println!(
+            "The {} army doesn't fit on the {} board.",
+            rules.army.name(),
+            rules.geometry.name
+        );
+        ::std::process::exit(1);
+    }
+
+    // The game and each computer player draw from their own seeds, which
+    // come from the game's, so that the game can be replayed from it unless
+    // the players think for a time.
+    let seed = seed.unwrap_or_else(random::new_seed);
+    let mut seeds = random::seeded(seed);
+    let red = player(&red, seeds.gen(), budget);
+    let blue = player(&blue, seeds.gen(), budget);
+    let rng = random::seeded(seeds.gen());
+
+    let library = match setups {
+        Some(path) => Library::open(path).unwrap_or_else(|e| {
+            println!("Could not open the setup library: {}", e);
+            ::std::process::exit(1)
+        }),
+        None => Library::built_in(),
+    };
+
     let termsize = termion::terminal_size().unwrap_or((40, 20));
-    if termsize.0 < 32 || termsize.1 < 12 {
-        println!("Stratagem requires a minimum terminal size of 32 x 12.");
+    let (width, height) = game::board_size(rules.geometry);
+    if termsize.0 < width || termsize.1 < height {
+        println!("Stratagem requires a minimum terminal size of {} x {}.", width, height);
         println!("Enlarge your terminal and try again.");
         ::std::process::exit(1);
     }
 
     let stdout = io::stdout();
-    let mut stdout = stdout.lock();
+    let stdout = stdout.lock();
     let stdin = io::stdin();
     let stdin = stdin.lock();
-    let stderr = io::stderr();
-    let mut stderr = stderr.lock();
-
     let stdout = stdout.into_raw_mode().unwrap();
 
-    match game::init(stdin, stdout, termsize).err() {
-        Some(error::Error::EarlyExit) => (),
-        Some(e) => {
+    let options = game::Options {
+        red,
+        blue,
+        archive,
+        library,
+        seed,
+        rng,
+        rules,
+    };
+    match game::init(stdin, stdout, termsize, options) {
+        Ok(result) => println!("Game over: {}", result),
+        Err(error::Error::EarlyExit) => (),
+        Err(e) => {
             println!("Fatal error: {}", e);
             ::std::process::exit(1)
         }
-        None => ()
     }
 }
+
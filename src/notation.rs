@@ -0,0 +1,166 @@
+//! Algebraic game notation, as used by digital Stratego interfaces for
+//! recording games.
+//!
+//! Ranks (rows) are numbered from 1 at the bottom of the board (Red's side)
+//! to the top, 10 on the classic board, and files (columns) lettered from
+//! left to right. Moves are
+//! written as the source square and destination square, separated by `-` for
+//! a move or `x` for a strike. Strikes may also give the ranks revealed, such
+//! as `major B2xcaptain B3`.
+
+use board::{Coord, Geometry, Move, Outcome, Piece};
+use error::NotationError;
+use rules::Numbering;
+
+/// How files are lettered.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Files {
+    /// Files A to J.
+    AToJ,
+    /// Files A to K, leaving out I.
+    AToK,
+}
+
+impl Files {
+    fn letters(&self) -> &'static str {
+        match *self {
+            Files::AToJ => "ABCDEFGHIJ",
+            Files::AToK => "ABCDEFGHJK",
+        }
+    }
+}
+
+/// A move read from notation, along with any ranks it revealed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Notated {
+    pub mv:       Move,
+    pub strike:   bool,
+    pub attacker: Option<Piece>,
+    pub defender: Option<Piece>,
+}
+
+impl From<&Outcome> for Notated {
+    /// The move as played, with the ranks revealed by any battle.
+    fn from(outcome: &Outcome) -> Self {
+        Notated {
+            mv:       outcome.mv,
+            strike:   outcome.strike.is_some(),
+            attacker: outcome.strike.map(|s| s.attacker),
+            defender: outcome.strike.map(|s| s.defender),
+        }
+    }
+}
+
+/// Writes a square of the board in notation, such as `A1` for the
+/// bottom-left corner.
+pub fn write_square(c: Coord, files: Files, geometry: Geometry) -> String {
+    let file = files.letters().as_bytes()[c.x as usize] as char;
+    format!("{}{}", file, geometry.height - c.y)
+}
+
+/// Reads a square of the board from notation. Files may be given in either
+/// case.
+pub fn read_square(
+    s: &str,
+    files: Files,
+    geometry: Geometry
+) -> Result<Coord, NotationError> {
+    let err = || NotationError::Square(s.to_string());
+    let mut chars = s.chars();
+    let file = chars.next().ok_or_else(err)?.to_ascii_uppercase();
+    let x = files
+        .letters()
+        .find(file)
+        .filter(|&x| x < geometry.width as usize)
+        .ok_or_else(err)?;
+    let rank = chars.as_str();
+    if !rank.chars().all(|c| c.is_ascii_digit()) {
+        return Err(err())
+    }
+    let rank = rank.parse::<u16>().map_err(|_| err())?;
+    if !(1 ..= geometry.height).contains(&rank) {
+        return Err(err())
+    }
+    Ok(Coord {x: x as u16, y: geometry.height - rank})
+}
+
+/// Writes a move in notation, giving the ranks revealed if it was a strike
+/// and they are known.
+pub fn write_move(notated: &Notated, files: Files, geometry: Geometry) -> String {
+    let side = |rank: Option<Piece>, c: Coord| match rank {
+        Some(p) => format!("{} {}", p.name(), write_square(c, files, geometry)),
+        None => write_square(c, files, geometry),
+    };
+    let from = side(notated.attacker, notated.mv.from);
+    let to = side(notated.defender, notated.mv.to);
+    if notated.strike {
+        format!("{}x{}", from, to)
+    } else {
+        format!("{}-{}", from, to)
+    }
+}
+
+/// Reads a move from notation. Revealed ranks may be given by name, or by
+/// number under the numbering.
+pub fn read_move(
+    s: &str,
+    files: Files,
+    geometry: Geometry,
+    numbering: Numbering
+) -> Result<Notated, NotationError> {
+    // The separator follows the attacker's square, rather than being the
+    // first x, which is also the modern marshall's symbol.
+    let err = || NotationError::Separator(s.to_string());
+    let file = s
+        .as_bytes()
+        .windows(2)
+        .position(|w| w[0].is_ascii_alphabetic() && w[1].is_ascii_digit())
+        .ok_or_else(err)?;
+    let sep = s[file + 1 ..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map(|i| file + 1 + i)
+        .ok_or_else(err)?;
+    let strike = match s[sep ..].chars().next() {
+        Some('-') => false,
+        Some('x') | Some('X') => true,
+        _ => return Err(err()),
+    };
+
+    let (attacker, from) = read_side(&s[.. sep], files, geometry, numbering)?;
+    let (defender, to) = read_side(&s[sep + 1 ..], files, geometry, numbering)?;
+    if !strike && (attacker.is_some() || defender.is_some()) {
+        return Err(NotationError::RankWithoutStrike(s.to_string()))
+    }
+
+    Ok(Notated {
+        mv: Move::new(from, to),
+        strike,
+        attacker,
+        defender,
+    })
+}
+
+/// Reads one side of a move: a square, optionally preceded by a rank.
+fn read_side(
+    s: &str,
+    files: Files,
+    geometry: Geometry,
+    numbering: Numbering
+) -> Result<(Option<Piece>, Coord), NotationError> {
+    let words = s.split_whitespace().collect::<Vec<_>>();
+    match words[..] {
+        [square] => Ok((None, read_square(square, files, geometry)?)),
+        [rank, square] => {
+            // Numbers are left to the numbering, as 1 is the marshall under
+            // one and the spy under the other.
+            let piece = Piece::from_symbol(rank, numbering)
+                .or_else(|| match rank.parse::<u8>() {
+                    Ok(_) => None,
+                    Err(_) => Piece::from(&rank.to_lowercase()),
+                })
+                .ok_or_else(|| NotationError::Rank(rank.to_string()))?;
+            Ok((Some(piece), read_square(square, files, geometry)?))
+        }
+        _ => Err(NotationError::Square(s.trim().to_string())),
+    }
+}
@@ -0,0 +1,89 @@
+//! The players controlling each side of a game.
+
+use std::collections::VecDeque;
+
+use board::{Board, Colour, GameResult, Move, Strike};
+use error::{Error, Result};
+use rules::Rules;
+use setup::Setup;
+use view::View;
+
+/// Something that can play one side of a game, such as a person at the
+/// terminal, a bot, or an opponent over the network.
+pub trait Player {
+    /// The name of the player, as shown in game records.
+    fn name(&self) -> String {"computer".to_string()}
+
+    /// Chooses where to place the player's pieces at the start of the game,
+    /// with the army and on the board of the rules.
+    fn setup(&mut self, colour: Colour, rules: Rules) -> Result<Setup>;
+
+    /// Chooses the player's next move, given what they can see of the game.
+    /// Only called on the player's turn.
+    fn choose_move(&mut self, view: &View) -> Result<Move>;
+
+    /// Called after every move in the game, by either player, with the
+    /// battle it caused, if any.
+    fn observe(&mut self, _mv: Move, _strike: Option<Strike>) {}
+}
+
+/// A player that follows a fixed script, for testing and replays.
+///
+/// Gives up with `Error::EarlyExit` once it runs out of moves.
+#[derive(Debug)]
+pub struct Scripted {
+    setup: Setup,
+    moves: VecDeque<Move>,
+}
+
+impl Scripted {
+    pub fn new(setup: Setup, moves: Vec<Move>) -> Self {
+        Scripted {
+            setup,
+            moves: moves.into(),
+        }
+    }
+}
+
+impl Player for Scripted {
+    fn name(&self) -> String {"scripted".to_string()}
+
+    fn setup(&mut self, _colour: Colour, _rules: Rules) -> Result<Setup> {
+        Ok(self.setup.clone())
+    }
+
+    fn choose_move(&mut self, _view: &View) -> Result<Move> {
+        self.moves.pop_front().ok_or(Error::EarlyExit)
+    }
+}
+
+/// Plays a game between two players without any front-end, from setup until
+/// the game is finished.
+///
+/// Fails if either player fails to move, or makes an illegal move.
+pub fn play_game(
+    board: &mut Board,
+    red: &mut dyn Player,
+    blue: &mut dyn Player
+) -> Result<GameResult> {
+    let rules = board.rules();
+    board.place(Colour::Red, &red.setup(Colour::Red, rules)?);
+    board.place(Colour::Blue, &blue.setup(Colour::Blue, rules)?);
+
+    loop {
+        let result = board.result();
+        if result != GameResult::Ongoing {
+            return Ok(result)
+        }
+
+        let colour = board.to_move();
+        let view = View::new(board, colour);
+        let mv = match colour {
+            Colour::Red  => red.choose_move(&view)?,
+            Colour::Blue => blue.choose_move(&view)?,
+        };
+        let outcome = board.try_move(mv)?;
+        red.observe(outcome.mv, outcome.strike);
+        blue.observe(outcome.mv, outcome.strike);
+    }
+}
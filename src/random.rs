@@ -0,0 +1,25 @@
+//! Seedable randomness, so that any randomised game can be reproduced.
+//!
+//! Every random decision, such as a random setup, should draw from a
+//! `GameRng` passed in by the caller rather than from `rand::thread_rng`.
+//! Games started from the same seed then play out the same way, and the seed
+//! is kept in the game record.
+
+use rand::{self, Isaac64Rng, SeedableRng};
+
+pub use rand::Rng;
+
+/// The random number generator used for every random decision in a game.
+///
+/// ISAAC-64 gives the same sequence on every platform for a given seed.
+pub type GameRng = Isaac64Rng;
+
+/// A generator that always produces the same sequence for the seed.
+pub fn seeded(seed: u64) -> GameRng {
+    Isaac64Rng::from_seed(&[seed][..])
+}
+
+/// A fresh seed, for when one hasn't been chosen.
+pub fn new_seed() -> u64 {
+    rand::thread_rng().gen()
+}
@@ -0,0 +1,297 @@
+//! Game records, for archiving finished games and resuming or replaying them.
+//!
+//! A record starts with tags giving details of the game, followed by every
+//! move in notation, one per line:
+//!
+//! ```text
+//! [Red "human"]
+//! [Blue "human"]
+//! [Date "2026.10.17"]
+//! [Variant "classic"]
+//! [Seed "2718281828"]
+//! [Result "red"]
+//! [Termination "flag captured"]
+//! [RedSetup "..."]
+//! [BlueSetup "..."]
+//!
+//! 1. A4-A5
+//! 1... J7-J6
+//! 2. scout A5xflag A7
+//! ```
+//!
+//! The result is one of `red`, `blue`, `draw`, or `*` for a game still in
+//! progress. Setups are written in the form read by `Setup::read`. The seed,
+//! if given, is the one every random decision in the game was drawn from.
+
+use std::fmt;
+
+use board::{Board, Colour, DrawReason, GameResult, Piece, Tile, WinReason};
+use error::RecordError;
+use notation::{self, Files, Notated};
+use rules::Rules;
+use setup::Setup;
+
+/// The full record of a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// The name of the red player.
+    pub red:        String,
+    /// The name of the blue player.
+    pub blue:       String,
+    /// The date the game was played, as `YYYY.MM.DD`.
+    pub date:       String,
+    pub variant:    String,
+    /// The seed of the game's random number generator, if it was recorded.
+    pub seed:       Option<u64>,
+    pub result:     GameResult,
+    pub red_setup:  Setup,
+    pub blue_setup: Setup,
+    /// Every move played, with the ranks revealed by battles.
+    pub moves:      Vec<Notated>,
+}
+
+impl Record {
+    /// Records a game, finished or in progress, as played today.
+    ///
+    /// Returns `None` if either player had not set up exactly the army of the
+    /// game's variant before the first move. The board doesn't know the
+    /// game's seed, so it is left for the caller to fill in.
+    pub fn from_board(board: &Board, red: &str, blue: &str) -> Option<Self> {
+        let start = board.initial();
+        let setup = |colour| {
+            Setup::from_board(&start, colour).filter(|s| board.rules().allows(s))
+        };
+        Some(Record {
+            red:        red.to_string(),
+            blue:       blue.to_string(),
+            date:       today(),
+            variant:    board.rules().variant(),
+            seed:       None,
+            result:     board.result(),
+            red_setup:  setup(Colour::Red)?,
+            blue_setup: setup(Colour::Blue)?,
+            moves:      board.history().iter().map(Notated::from).collect(),
+        })
+    }
+
+    /// Reads a record in the form written by `Display`.
+    ///
+    /// Unknown tags are ignored.
+    pub fn read(s: &str) -> Result<Self, RecordError> {
+        let mut tags = vec![];
+        let mut move_lines = vec![];
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue
+            }
+            let invalid = || RecordError::InvalidLine(n + 1, line.to_string());
+
+            if line.starts_with('[') {
+                let (name, value) = read_tag(line).ok_or_else(invalid)?;
+                tags.push((name, value));
+            } else {
+                // Strip the move number, such as `12.` or `12...`.
+                let mut parts = line.splitn(2, ' ');
+                let number = parts.next().unwrap_or("");
+                let mv = parts.next().ok_or_else(invalid)?;
+                let digits = number.trim_end_matches('.');
+                if digits.is_empty()
+                    || !number.ends_with('.')
+                    || !digits.chars().all(|c| c.is_ascii_digit())
+                {
+                    return Err(invalid())
+                }
+                move_lines.push(mv.trim());
+            }
+        }
+
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(t, _)| t == name)
+                .map(|(_, v)| v.clone())
+                .ok_or_else(|| RecordError::MissingTag(name.to_string()))
+        };
+        let setup = |name: &str| {
+            let value = tag(name)?;
+            Setup::read(&value)
+                .map_err(|_| RecordError::InvalidTag(name.to_string(), value))
+        };
+
+        // Moves are read once the board is known from the variant.
+        let variant = tag("Variant")?;
+        let rules = variant_rules(&variant);
+        let mut moves = vec![];
+        for mv in move_lines {
+            let notated = notation::read_move(mv, Files::AToJ, rules.geometry, rules.numbering)
+                .map_err(|e| RecordError::InvalidMove(moves.len() + 1, e))?;
+            moves.push(notated);
+        }
+
+        let seed = match tag("Seed") {
+            Ok(seed) => Some(seed.parse().map_err(|_| {
+                RecordError::InvalidTag("Seed".to_string(), seed.clone())
+            })?),
+            Err(_) => None,
+        };
+
+        let result = tag("Result")?;
+        let result = match &result[..] {
+            "*" => GameResult::Ongoing,
+            winner => {
+                let reason = tag("Termination")?;
+                let invalid =
+                    || RecordError::InvalidTag("Termination".to_string(), reason.clone());
+                match winner {
+                    "red" => GameResult::Win(
+                        Colour::Red,
+                        WinReason::from(&reason).ok_or_else(invalid)?,
+                    ),
+                    "blue" => GameResult::Win(
+                        Colour::Blue,
+                        WinReason::from(&reason).ok_or_else(invalid)?,
+                    ),
+                    "draw" => GameResult::Draw(
+                        DrawReason::from(&reason).ok_or_else(invalid)?,
+                    ),
+                    _ => return Err(RecordError::InvalidTag(
+                        "Result".to_string(),
+                        result.clone(),
+                    )),
+                }
+            }
+        };
+
+        Ok(Record {
+            red: tag("Red")?,
+            blue: tag("Blue")?,
+            date: tag("Date")?,
+            variant,
+            seed,
+            result,
+            red_setup: setup("RedSetup")?,
+            blue_setup: setup("BlueSetup")?,
+            moves,
+        })
+    }
+
+    /// Sets up a new board with the rules of the game's variant, and replays
+    /// the first `ply` moves of the game onto it, checking that each battle
+    /// turns out as recorded.
+    pub fn board_at(&self, ply: usize) -> Result<Board, RecordError> {
+        let rules = Rules::from_variant(&self.variant).ok_or_else(|| {
+            RecordError::InvalidTag("Variant".to_string(), self.variant.clone())
+        })?;
+        for &(tag, setup) in &[
+            ("RedSetup", &self.red_setup),
+            ("BlueSetup", &self.blue_setup),
+        ] {
+            if !rules.allows(setup) {
+                return Err(RecordError::InvalidTag(tag.to_string(), setup.to_string()))
+            }
+        }
+
+        let mut board = Board::with_rules(rules);
+        board.place(Colour::Red, &self.red_setup);
+        board.place(Colour::Blue, &self.blue_setup);
+
+        for (n, notated) in self.moves.iter().take(ply).enumerate() {
+            let is_strike = matches!(board.tile_at(notated.mv.to), Tile::Piece(..));
+            if is_strike != notated.strike {
+                return Err(RecordError::Mismatch(n + 1))
+            }
+
+            let outcome = board
+                .try_move(notated.mv)
+                .map_err(|e| RecordError::IllegalMove(n + 1, e))?;
+            let played = Notated::from(&outcome);
+            let matches = |recorded: Option<Piece>, actual: Option<Piece>| {
+                recorded.is_none() || recorded == actual
+            };
+            if !matches(notated.attacker, played.attacker)
+                || !matches(notated.defender, played.defender)
+            {
+                return Err(RecordError::Mismatch(n + 1))
+            }
+        }
+        Ok(board)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (result, termination) = match self.result {
+            GameResult::Win(c, r) => (c.to_string(), Some(r.to_string())),
+            GameResult::Draw(r)   => ("draw".to_string(), Some(r.to_string())),
+            GameResult::Ongoing   => ("*".to_string(), None),
+        };
+
+        writeln!(f, "[Red \"{}\"]", self.red)?;
+        writeln!(f, "[Blue \"{}\"]", self.blue)?;
+        writeln!(f, "[Date \"{}\"]", self.date)?;
+        writeln!(f, "[Variant \"{}\"]", self.variant)?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "[Seed \"{}\"]", seed)?;
+        }
+        writeln!(f, "[Result \"{}\"]", result)?;
+        if let Some(t) = termination {
+            writeln!(f, "[Termination \"{}\"]", t)?;
+        }
+        writeln!(f, "[RedSetup \"{}\"]", self.red_setup)?;
+        writeln!(f, "[BlueSetup \"{}\"]", self.blue_setup)?;
+        writeln!(f)?;
+
+        let geometry = variant_rules(&self.variant).geometry;
+        for (n, notated) in self.moves.iter().enumerate() {
+            let dots = if n % 2 == 0 { "." } else { "..." };
+            writeln!(
+                f,
+                "{}{} {}",
+                n / 2 + 1,
+                dots,
+                notation::write_move(notated, Files::AToJ, geometry)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The rules of the variant, or the classic rules if the variant isn't
+/// known, for reading and writing moves.
+fn variant_rules(variant: &str) -> Rules {
+    Rules::from_variant(variant).unwrap_or_default()
+}
+
+/// Reads a tag line such as `[Red "human"]`.
+fn read_tag(line: &str) -> Option<(String, String)> {
+    if !line.starts_with('[') || !line.ends_with("\"]") {
+        return None
+    }
+    let inner = &line[1 .. line.len() - 2];
+    let quote = inner.find(" \"")?;
+    Some((inner[.. quote].to_string(), inner[quote + 2 ..].to_string()))
+}
+
+/// Today's date in UTC, as `YYYY.MM.DD`.
+fn today() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // Convert days since the epoch to a civil date, per Howard Hinnant's
+    // `civil_from_days`.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,230 @@
+//! The options that differ between rule sets, and tournament rules on
+//! repetition, which depend on the history of the game rather than just the
+//! position of the pieces.
+//!
+//! The repetition rules follow the International Stratego Federation's
+//! two-square and more-square rules, along with the conditions for a drawn
+//! game.
+
+use board::{Colour, Coord, Geometry, Move, Outcome};
+use setup::{Army, Setup};
+
+/// How ranks are numbered when shown or read.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Numbering {
+    /// The original numbering, from the marshall at 1 down to the scout at
+    /// 9, with the spy as S.
+    Classic,
+    /// The newer numbering, from the marshall at 10 down to the spy at 1.
+    /// The marshall is shown as X so that every rank fits in one character.
+    Modern,
+}
+
+/// The options that vary between rule sets. The default is the classic game
+/// as played in tournaments.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rules {
+    /// The pieces each player starts with.
+    pub army:              Army,
+    /// The board the game is played on.
+    pub geometry:          Geometry,
+    pub numbering:         Numbering,
+    /// Whether a scout may move more than one tile and strike in the same
+    /// turn. Older rule sets only let scouts strike an adjacent piece.
+    pub scout_strikes:     bool,
+    /// Whether a defender that wins a battle moves onto the tile the
+    /// attacker came from, as in the original rules.
+    pub defender_advances: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            army:              Army::Classic,
+            geometry:          Geometry::CLASSIC,
+            numbering:         Numbering::Classic,
+            scout_strikes:     true,
+            defender_advances: false,
+        }
+    }
+}
+
+impl Rules {
+    /// The name of the variant these rules play, as kept in game records:
+    /// the name of the army, such as `classic` or `barrage`, followed by any
+    /// options that differ from the default, such as the `duel` board.
+    ///
+    /// Numbering only changes how ranks are shown, so it isn't included.
+    pub fn variant(&self) -> String {
+        let mut name = self.army.name().to_string();
+        if self.geometry != Geometry::CLASSIC {
+            name += " ";
+            name += self.geometry.name;
+        }
+        if !self.scout_strikes {
+            name += " no-scout-strikes";
+        }
+        if self.defender_advances {
+            name += " defender-advances";
+        }
+        name
+    }
+
+    /// Reads rules from the name of their variant, if the army fits on the
+    /// board.
+    pub fn from_variant(s: &str) -> Option<Self> {
+        let mut words = s.split_whitespace();
+        let mut rules = Rules {
+            army: Army::from(words.next()?)?,
+            ..Rules::default()
+        };
+        for word in words {
+            match word {
+                "no-scout-strikes"  => rules.scout_strikes = false,
+                "defender-advances" => rules.defender_advances = true,
+                _ => rules.geometry = Geometry::from(word)?,
+            }
+        }
+        Some(rules).filter(Rules::army_fits)
+    }
+
+    /// Whether the army fits in the setup area of the board.
+    pub fn army_fits(&self) -> bool {
+        self.army.pieces().len() <= self.geometry.setup_squares()
+    }
+
+    /// Whether the setup places exactly the army, on this board.
+    pub fn allows(&self, setup: &Setup) -> bool {
+        setup.army() == Some(self.army) && setup.geometry() == Some(self.geometry)
+    }
+}
+
+/// The number of consecutive moves a piece may make between the same two
+/// squares.
+const TWO_SQUARE_LIMIT: usize = 3;
+
+/// Whether playing `m` as `player` would break the two-square rule.
+///
+/// A piece may not move back and forth between the same two squares more than
+/// three times in a row, regardless of what the opponent does in between.
+/// Attacking moves count towards the limit.
+///
+/// For scouts, the "two squares" are the whole stretch covered by the first
+/// move of the sequence: once a scout has shuffled back and forth three times,
+/// it may not end its next move anywhere on that stretch.
+pub fn breaks_two_square(history: &[Outcome], m: &Move, player: Colour) -> bool {
+    let own = history
+        .iter()
+        .rev()
+        .filter(|o| o.colour == player)
+        .take(TWO_SQUARE_LIMIT)
+        .map(|o| o.mv)
+        .collect::<Vec<_>>();
+    if own.len() < TWO_SQUARE_LIMIT {
+        return false
+    }
+
+    // `own` is most recent first; the sequence is checked oldest first,
+    // ending with the proposed move.
+    let first = own[TWO_SQUARE_LIMIT - 1];
+    let dir = direction(&first);
+    let mut prev = first;
+    for (n, next) in own.iter().rev().skip(1).chain(Some(m)).enumerate() {
+        // The same piece must be moving the whole time, alternating
+        // direction along the first move's stretch.
+        let expected = if n % 2 == 0 { (-dir.0, -dir.1) } else { dir };
+        if next.from != prev.to
+            || direction(next) != expected
+            || !within(&first, next.to)
+        {
+            return false
+        }
+        prev = *next;
+    }
+    true
+}
+
+/// The unit direction of a move along each axis.
+fn direction(m: &Move) -> (isize, isize) {
+    (
+        (m.to.x as isize - m.from.x as isize).signum(),
+        (m.to.y as isize - m.from.y as isize).signum(),
+    )
+}
+
+/// Whether the coordinate lies on the stretch of tiles covered by the move,
+/// inclusive of both ends.
+fn within(m: &Move, c: Coord) -> bool {
+    let between = |a: u16, b: u16, v: u16| a.min(b) <= v && v <= a.max(b);
+    between(m.from.x, m.to.x, c.x) && between(m.from.y, m.to.y, c.y)
+}
+
+/// Whether playing `m` as `player` would break the more-square rule.
+///
+/// A player may not endlessly chase one of the opponent's pieces. A chase is
+/// a continuous sequence where one piece moves next to an enemy piece, which
+/// then moves away, and so on. Once a chase is underway, the chasing piece may
+/// not make a move that carries on the chase and results in a position that
+/// has already occurred during it.
+///
+/// Only the chasing and chased pieces move during a chase, so positions are
+/// compared by where those two pieces stand.
+pub fn breaks_more_square(history: &[Outcome], m: &Move, player: Colour) -> bool {
+    let chased = match history.last() {
+        Some(o) if o.colour != player && o.strike.is_none() => o.mv.to,
+        _ => return false,
+    };
+    if !adjacent(m.to, chased) {
+        return false
+    }
+
+    // Walk back through the chase, collecting the positions after each of
+    // the chaser's moves.
+    let (mut chaser_at, mut chased_at) = (m.from, chased);
+    let mut positions = vec![];
+    for pair in history.rchunks(2) {
+        let (own, theirs) = match *pair {
+            [own, theirs] => (own, theirs),
+            _ => break,
+        };
+        if own.colour != player
+            || own.strike.is_some()
+            || theirs.strike.is_some()
+            || own.mv.to != chaser_at
+            || theirs.mv.to != chased_at
+            || !adjacent(own.mv.to, theirs.mv.from)
+        {
+            break
+        }
+        positions.push((own.mv.to, theirs.mv.from));
+        chaser_at = own.mv.from;
+        chased_at = theirs.mv.from;
+    }
+
+    positions.contains(&(m.to, chased))
+}
+
+/// How many times the latest position has occurred with the same player to
+/// move, including the latest occurrence.
+///
+/// `positions` has the position before the first move, followed by the
+/// position after each move, so that the same player is to move in every
+/// second entry.
+pub fn repetitions<T: PartialEq>(positions: &[T]) -> usize {
+    match positions.last() {
+        Some(latest) => positions
+            .iter()
+            .rev()
+            .step_by(2)
+            .filter(|p| *p == latest)
+            .count(),
+        None => 0,
+    }
+}
+
+/// Whether two tiles are orthogonally adjacent.
+fn adjacent(a: Coord, b: Coord) -> bool {
+    let dx = (a.x as isize - b.x as isize).abs();
+    let dy = (a.y as isize - b.y as isize).abs();
+    dx + dy == 1
+}
@@ -0,0 +1,200 @@
+//! Starting placements of a player's pieces.
+
+use std::fmt;
+
+use rand::Rng;
+
+use board::{Board, Colour, Geometry, Piece, Tile};
+use error::SetupError;
+use rules::Rules;
+
+/// The pieces each player starts the classic game with.
+pub const ARMY: [Piece; 40] = {
+    use board::Piece::*;
+    [
+        Bomb, Bomb, Bomb, Bomb, Bomb, Bomb, Marshall, General, Colonel,
+        Colonel, Major, Major, Major, Captain, Captain, Captain, Captain,
+        Lieutenant, Lieutenant, Lieutenant, Lieutenant, Sergeant, Sergeant,
+        Sergeant, Sergeant, Miner, Miner, Miner, Miner, Miner, Scout, Scout,
+        Scout, Scout, Scout, Scout, Scout, Scout, Spy, Flag
+    ]
+};
+
+/// The pieces each player starts a game of Barrage with.
+pub const BARRAGE_ARMY: [Piece; 8] = {
+    use board::Piece::*;
+    [Bomb, Marshall, General, Miner, Scout, Scout, Spy, Flag]
+};
+
+/// A standard setup, used when a player doesn't choose their own.
+pub const DEFAULT_SETUP: &str = "5879896958949FS7458968BB2B7B3973B4561B96";
+
+/// The symbol for a square left empty in a written setup.
+const EMPTY_SQUARE: char = '.';
+
+/// The pieces each player starts with, which differ between variants.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Army {
+    /// The full army of 40 pieces, filling the setup area of the classic
+    /// board.
+    Classic,
+    /// Barrage, with 8 pieces placed anywhere in the setup area.
+    Barrage,
+}
+
+impl Army {
+    pub const ALL: [Army; 2] = [Army::Classic, Army::Barrage];
+
+    pub fn pieces(&self) -> &'static [Piece] {
+        match *self {
+            Army::Classic => &ARMY,
+            Army::Barrage => &BARRAGE_ARMY,
+        }
+    }
+
+    /// The name of the army, as used in variant names.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Army::Classic => "classic",
+            Army::Barrage => "barrage",
+        }
+    }
+
+    pub fn from(s: &str) -> Option<Army> {
+        Army::ALL.iter().cloned().find(|a| a.name() == s)
+    }
+
+    /// Checks that the pieces are exactly those in the army.
+    fn check(&self, pieces: &[Piece]) -> Result<(), SetupError> {
+        for piece in self.pieces().iter().chain(pieces) {
+            let expected = self.pieces().iter().filter(|&p| p == piece).count();
+            let found = pieces.iter().filter(|&p| p == piece).count();
+            if found != expected {
+                return Err(SetupError::Army(*piece, found, expected))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The placement of one player's pieces at the start of the game, with
+/// `None` for squares left empty.
+///
+/// Rows run from the player's back row to their front line, and columns from
+/// the player's left to their right, so the same setup can be used by either
+/// colour. There are as many as the board has setup rows and columns.
+///
+/// Setups are written as one character per square in the same order, using
+/// the symbols pieces are displayed with and `.` for an empty square, such as
+/// `5879896958949FS7458968BB2B7B3973B4561B96` on the classic board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setup {
+    pub rows: Vec<Vec<Option<Piece>>>,
+}
+
+impl Setup {
+    /// Places the army on random squares of the setup area of the rules'
+    /// board.
+    pub fn random<R: Rng>(rules: Rules, rng: &mut R) -> Self {
+        let mut squares = vec![None; rules.geometry.setup_squares()];
+        for (square, piece) in squares.iter_mut().zip(rules.army.pieces()) {
+            *square = Some(*piece);
+        }
+        rng.shuffle(&mut squares);
+        Setup::from_squares(&squares, rules.geometry)
+    }
+
+    /// Reads a setup from its written form, checking that it fills the setup
+    /// area of one of the boards and places exactly the pieces in one of the
+    /// armies. Whitespace between squares is ignored, and symbols may be
+    /// given in either case.
+    pub fn read(s: &str) -> Result<Self, SetupError> {
+        let squares = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                EMPTY_SQUARE => Ok(None),
+                _ => Piece::from(&c.to_string())
+                    .or_else(|| Piece::from(&c.to_ascii_uppercase().to_string()))
+                    .map(Some)
+                    .ok_or(SetupError::Symbol(c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let geometry = Geometry::ALL
+            .iter()
+            .cloned()
+            .find(|g| g.setup_squares() == squares.len())
+            .ok_or(SetupError::Length(squares.len()))?;
+
+        let pieces = squares.iter().flatten().cloned().collect::<Vec<_>>();
+        let army = Army::ALL
+            .iter()
+            .find(|a| a.pieces().len() == pieces.len())
+            .ok_or(SetupError::Count(pieces.len()))?;
+        army.check(&pieces)?;
+        Ok(Setup::from_squares(&squares, geometry))
+    }
+
+    /// The army the setup places, if it places exactly the pieces of one.
+    pub fn army(&self) -> Option<Army> {
+        let pieces = self.pieces().collect::<Vec<_>>();
+        Army::ALL.iter().cloned().find(|a| a.check(&pieces).is_ok())
+    }
+
+    /// The board the setup is for, if it fits the setup area of one.
+    pub fn geometry(&self) -> Option<Geometry> {
+        Geometry::ALL.iter().cloned().find(|g| {
+            self.rows.len() == g.setup_rows as usize
+                && self.rows.iter().all(|row| row.len() == g.width as usize)
+        })
+    }
+
+    /// The pieces placed, in order.
+    pub fn pieces<'a>(&'a self) -> impl Iterator<Item = Piece> + 'a {
+        self.rows.iter().flat_map(|row| row.iter()).flatten().cloned()
+    }
+
+    /// Reads the colour's setup back off the board, if its setup area holds
+    /// only its own pieces and empty tiles.
+    pub fn from_board(board: &Board, colour: Colour) -> Option<Self> {
+        let geometry = board.geometry();
+        let mut rows =
+            vec![vec![None; geometry.width as usize]; geometry.setup_rows as usize];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, piece) in row.iter_mut().enumerate() {
+                match board.tile_at(geometry.setup_coord(colour, r, c)) {
+                    Tile::Piece(p, col) if col == colour => *piece = Some(p),
+                    Tile::Empty => (),
+                    _ => return None,
+                }
+            }
+        }
+        Some(Setup { rows })
+    }
+
+    fn from_squares(squares: &[Option<Piece>], geometry: Geometry) -> Self {
+        let rows = squares
+            .chunks(geometry.width as usize)
+            .map(|row| row.to_vec())
+            .collect();
+        Setup { rows }
+    }
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Setup::read(DEFAULT_SETUP).unwrap()
+    }
+}
+
+impl fmt::Display for Setup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for square in self.rows.iter().flat_map(|row| row.iter()) {
+            match *square {
+                Some(piece) => write!(f, "{}", piece)?,
+                None => write!(f, "{}", EMPTY_SQUARE)?,
+            }
+        }
+        Ok(())
+    }
+}
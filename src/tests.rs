@@ -1,5 +1,5 @@
-#[allow(unused_imports)]
 use board::*;
+use setup::{Army, Setup, ARMY};
 
 #[test]
 fn test_move_basic() {
@@ -20,14 +20,14 @@ fn test_move_basic() {
 #[test]
 fn test_move_into_terrain() {
     let mut board = Board::new();
-    let e1 = Coord::from("e1").unwrap();
-    board.set_tile(e1, Tile::Piece(Piece::Marshall, Colour::Red));
+    let c3 = Coord::from("c3").unwrap();
+    board.set_tile(c3, Tile::Piece(Piece::Marshall, Colour::Red));
 
-    let moves = board.find_moves(e1);
+    let moves = board.find_moves(c3);
     let expected = vec![
-        Move::new(e1, Coord::from("f1").unwrap()),
-        Move::new(e1, Coord::from("d1").unwrap()),
-        Move::new(e1, Coord::from("e0").unwrap()),
+        Move::new(c3, Coord::from("d3").unwrap()),
+        Move::new(c3, Coord::from("b3").unwrap()),
+        Move::new(c3, Coord::from("c2").unwrap()),
     ];
 
     assert_eq!(moves, expected)
@@ -67,30 +67,1576 @@ fn test_move_bomb() {
 #[test]
 fn test_move_scout() {
     let mut board = Board::new();
-    let e0 = Coord::from("e0").unwrap();
+    let g2 = Coord::from("g2").unwrap();
 
-    board.set_tile(e0, Tile::Piece(Piece::Scout, Colour::Red));
-    // Ally three spaces above; should stop before.
+    board.set_tile(g2, Tile::Piece(Piece::Scout, Colour::Red));
+    // Ally three spaces right; should stop before.
     board.set_tile(
-        Coord::from("h0").unwrap(),
+        Coord::from("j2").unwrap(),
         Tile::Piece(Piece::Flag, Colour::Red),
     );
-    // Enemy three spaces below; should capture.
+    // Enemy three spaces left; should capture.
     board.set_tile(
-        Coord::from("b0").unwrap(),
+        Coord::from("d2").unwrap(),
         Tile::Piece(Piece::Flag, Colour::Blue),
     );
-    // Terrain two spaces right; should stop before.
+    // Terrain two spaces below; should stop before.
+    // Nothing above; should go to the edge of the board.
 
-    let moves = board.find_moves(e0);
+    let moves = board.find_moves(g2);
     let expected = vec![
-        Move::new(e0, Coord::from("f0").unwrap()),
-        Move::new(e0, Coord::from("g0").unwrap()),
-        Move::new(e0, Coord::from("d0").unwrap()),
-        Move::new(e0, Coord::from("c0").unwrap()),
-        Move::new(e0, Coord::from("b0").unwrap()),
-        Move::new(e0, Coord::from("e1").unwrap()),
+        Move::new(g2, Coord::from("h2").unwrap()),
+        Move::new(g2, Coord::from("i2").unwrap()),
+        Move::new(g2, Coord::from("f2").unwrap()),
+        Move::new(g2, Coord::from("e2").unwrap()),
+        Move::new(g2, Coord::from("d2").unwrap()),
+        Move::new(g2, Coord::from("g3").unwrap()),
+        Move::new(g2, Coord::from("g1").unwrap()),
+        Move::new(g2, Coord::from("g0").unwrap()),
     ];
 
     assert_eq!(moves, expected);
 }
+
+#[test]
+fn test_try_move_legal() {
+    let mut board = Board::new();
+    let e0 = Coord::from("e0").unwrap();
+    let e1 = Coord::from("e1").unwrap();
+    board.set_tile(e0, Tile::Piece(Piece::Marshall, Colour::Red));
+
+    assert!(board.try_move(Move::new(e0, e1)).is_ok());
+    assert_eq!(board.tile_at(e1), Tile::Piece(Piece::Marshall, Colour::Red));
+    assert_eq!(board.tile_at(e0), Tile::Empty);
+    assert_eq!(board.to_move(), Colour::Blue);
+}
+
+#[test]
+fn test_try_move_rejections() {
+    use error::IllegalMove;
+
+    let mut board = Board::new();
+    let a0 = Coord::from("a0").unwrap();
+    let b0 = Coord::from("b0").unwrap();
+    let c0 = Coord::from("c0").unwrap();
+    let j9 = Coord::from("j9").unwrap();
+    board.set_tile(a0, Tile::Piece(Piece::Bomb, Colour::Red));
+    board.set_tile(b0, Tile::Piece(Piece::Sergeant, Colour::Red));
+    board.set_tile(j9, Tile::Piece(Piece::Sergeant, Colour::Blue));
+
+    assert_eq!(
+        board.try_move(Move::new(c0, b0)),
+        Err(IllegalMove::EmptyTile)
+    );
+    assert_eq!(
+        board.try_move(Move::new(Coord::from("c4").unwrap(), c0)),
+        Err(IllegalMove::Terrain)
+    );
+    assert_eq!(
+        board.try_move(Move::new(a0, Coord::from("a1").unwrap())),
+        Err(IllegalMove::Immobile(Piece::Bomb))
+    );
+    assert_eq!(
+        board.try_move(Move::new(b0, a0)),
+        Err(IllegalMove::AlliedPiece)
+    );
+    assert_eq!(
+        board.try_move(Move::new(b0, Coord::from("d0").unwrap())),
+        Err(IllegalMove::NotAdjacent)
+    );
+    assert_eq!(
+        board.try_move(Move::new(j9, Coord::from("j8").unwrap())),
+        Err(IllegalMove::OutOfTurn(Colour::Red))
+    );
+    assert_eq!(
+        board.try_move(Move::new(b0, Coord { x: 1, y: 10 })),
+        Err(IllegalMove::OutOfBounds)
+    );
+}
+
+#[test]
+fn test_try_move_scout_path() {
+    use error::IllegalMove;
+
+    let mut board = Board::new();
+    let c0 = Coord::from("c0").unwrap();
+    board.set_tile(c0, Tile::Piece(Piece::Scout, Colour::Red));
+    board.set_tile(
+        Coord::from("f0").unwrap(),
+        Tile::Piece(Piece::Miner, Colour::Blue),
+    );
+
+    // Diagonal.
+    assert_eq!(
+        board.try_move(Move::new(c0, Coord::from("d1").unwrap())),
+        Err(IllegalMove::InvalidScoutPath)
+    );
+    // Leaping over an enemy piece.
+    assert_eq!(
+        board.try_move(Move::new(c0, Coord::from("g0").unwrap())),
+        Err(IllegalMove::InvalidScoutPath)
+    );
+    // Crossing the lakes.
+    assert_eq!(
+        board.try_move(Move::new(c0, Coord::from("c9").unwrap())),
+        Err(IllegalMove::InvalidScoutPath)
+    );
+    assert!(board.try_move(Move::new(c0, Coord::from("c3").unwrap())).is_ok());
+}
+
+#[test]
+fn test_outcome_plain_move() {
+    let mut board = Board::new();
+    let e0 = Coord::from("e0").unwrap();
+    let e1 = Coord::from("e1").unwrap();
+    board.set_tile(e0, Tile::Piece(Piece::Major, Colour::Red));
+
+    let outcome = board.try_move(Move::new(e0, e1)).unwrap();
+    assert_eq!(outcome.strike, None);
+    assert_eq!(outcome.revealed(), vec![]);
+    assert_eq!(outcome.casualties(), vec![]);
+    assert_eq!(outcome.survivor(), Some(e1));
+    assert_eq!(board.history(), &[outcome]);
+}
+
+#[test]
+fn test_outcome_strikes() {
+    let e0 = Coord::from("e0").unwrap();
+    let e1 = Coord::from("e1").unwrap();
+    let cases = vec![
+        (Piece::Major, Piece::Captain, BattleResult::Victory),
+        (Piece::Major, Piece::Colonel, BattleResult::Loss),
+        (Piece::Major, Piece::Major, BattleResult::Draw),
+        (Piece::Spy, Piece::Marshall, BattleResult::Victory),
+        (Piece::Miner, Piece::Bomb, BattleResult::Victory),
+    ];
+
+    for (attacker, defender, result) in cases {
+        let mut board = Board::new();
+        board.set_tile(e0, Tile::Piece(attacker, Colour::Red));
+        board.set_tile(e1, Tile::Piece(defender, Colour::Blue));
+
+        let outcome = board.try_move(Move::new(e0, e1)).unwrap();
+        assert_eq!(
+            outcome.strike,
+            Some(Strike { attacker, defender, result })
+        );
+        assert_eq!(
+            outcome.revealed(),
+            vec![(e0, attacker, Colour::Red), (e1, defender, Colour::Blue)]
+        );
+        assert_eq!(board.tile_at(e0), Tile::Empty);
+
+        match result {
+            BattleResult::Victory => {
+                assert_eq!(outcome.survivor(), Some(e1));
+                assert_eq!(outcome.casualties(), vec![(e1, defender, Colour::Blue)]);
+                assert_eq!(board.tile_at(e1), Tile::Piece(attacker, Colour::Red));
+            }
+            BattleResult::Loss => {
+                assert_eq!(outcome.survivor(), Some(e1));
+                assert_eq!(outcome.casualties(), vec![(e0, attacker, Colour::Red)]);
+                assert_eq!(board.tile_at(e1), Tile::Piece(defender, Colour::Blue));
+            }
+            BattleResult::Draw => {
+                assert_eq!(outcome.survivor(), None);
+                assert_eq!(outcome.casualties().len(), 2);
+                assert_eq!(board.tile_at(e1), Tile::Empty);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_result_flag_captured() {
+    let mut board = Board::new();
+    let e0 = Coord::from("e0").unwrap();
+    let e1 = Coord::from("e1").unwrap();
+    board.set_tile(e0, Tile::Piece(Piece::Flag, Colour::Blue));
+    board.set_tile(e1, Tile::Piece(Piece::Scout, Colour::Red));
+    board.set_tile(
+        Coord::from("a9").unwrap(),
+        Tile::Piece(Piece::Flag, Colour::Red),
+    );
+    assert_eq!(board.result(), GameResult::Ongoing);
+
+    board.try_move(Move::new(e1, e0)).unwrap();
+    assert_eq!(
+        board.result(),
+        GameResult::Win(Colour::Red, WinReason::FlagCaptured)
+    );
+}
+
+#[test]
+fn test_result_no_moves() {
+    let mut board = Board::new();
+    let e8 = Coord::from("e8").unwrap();
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Flag, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("a9").unwrap(),
+        Tile::Piece(Piece::Flag, Colour::Red),
+    );
+    board.set_tile(e8, Tile::Piece(Piece::Miner, Colour::Red));
+    assert_eq!(board.result(), GameResult::Ongoing);
+
+    // Blue's only movable piece is walled in by its own bombs.
+    board.set_tile(Coord::from("j0").unwrap(), Tile::Piece(Piece::Scout, Colour::Blue));
+    board.set_tile(Coord::from("i0").unwrap(), Tile::Piece(Piece::Bomb, Colour::Blue));
+    board.set_tile(Coord::from("j1").unwrap(), Tile::Piece(Piece::Bomb, Colour::Blue));
+
+    board.try_move(Move::new(e8, Coord::from("e7").unwrap())).unwrap();
+    assert_eq!(
+        board.result(),
+        GameResult::Win(Colour::Red, WinReason::NoMoves)
+    );
+}
+
+fn mv(from: &str, to: &str) -> Move {
+    Move::new(Coord::from(from).unwrap(), Coord::from(to).unwrap())
+}
+
+/// Blue moves that never repeat a pair of squares, for use while testing Red's
+/// repetition.
+const BLUE_LOOP: [(&str, &str); 4] =
+    [("a0", "a1"), ("a1", "b1"), ("b1", "b0"), ("b0", "a0")];
+
+/// Plays the Red moves, with a move from `BLUE_LOOP` after each.
+fn play_red(board: &mut Board, moves: &[(&str, &str)]) {
+    for &(from, to) in moves {
+        board.try_move(mv(from, to)).unwrap();
+        let n = board.history().len() / 2;
+        let (from, to) = BLUE_LOOP[n % BLUE_LOOP.len()];
+        board.try_move(mv(from, to)).unwrap();
+    }
+}
+
+fn two_square_board() -> Board {
+    let mut board = Board::new();
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Sergeant, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("e8").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("a8").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Red),
+    );
+    board
+}
+
+#[test]
+fn test_two_square_limit() {
+    use error::IllegalMove;
+
+    let mut board = two_square_board();
+    play_red(&mut board, &[("e8", "e7"), ("e7", "e8")]);
+    // A third move between the squares is allowed...
+    assert!(board.find_moves(Coord::from("e8").unwrap()).contains(&mv("e8", "e7")));
+    play_red(&mut board, &[("e8", "e7")]);
+
+    // ...but a fourth is not.
+    let moves = board.find_moves(Coord::from("e7").unwrap());
+    assert!(!moves.contains(&mv("e7", "e8")));
+    assert!(moves.contains(&mv("e7", "e6")));
+    assert_eq!(
+        board.try_move(mv("e7", "e8")),
+        Err(IllegalMove::TwoSquareRule)
+    );
+}
+
+#[test]
+fn test_two_square_reset_by_other_piece() {
+    let mut board = two_square_board();
+    play_red(
+        &mut board,
+        &[("e8", "e7"), ("e7", "e8"), ("a8", "a7"), ("e8", "e7")],
+    );
+    assert!(board.find_moves(Coord::from("e7").unwrap()).contains(&mv("e7", "e8")));
+}
+
+#[test]
+fn test_two_square_reset_by_third_square() {
+    let mut board = two_square_board();
+    play_red(
+        &mut board,
+        &[("e8", "e7"), ("e7", "e8"), ("e8", "e7"), ("e7", "e6"), ("e6", "e7")],
+    );
+    // The piece has moved between e6 and e7 twice, and e7 and e8 three times
+    // but not consecutively.
+    let moves = board.find_moves(Coord::from("e7").unwrap());
+    assert!(moves.contains(&mv("e7", "e6")));
+    assert!(moves.contains(&mv("e7", "e8")));
+}
+
+#[test]
+fn test_two_square_scout_stretch() {
+    let mut board = two_square_board();
+    play_red(&mut board, &[("a8", "a5"), ("a5", "a7"), ("a7", "a5")]);
+
+    // The scout may not land anywhere on the stretch of the first move...
+    let moves = board.find_moves(Coord::from("a5").unwrap());
+    assert!(!moves.contains(&mv("a5", "a6")));
+    assert!(!moves.contains(&mv("a5", "a7")));
+    assert!(!moves.contains(&mv("a5", "a8")));
+    // ...but may pass beyond it, or leave it sideways.
+    assert!(moves.contains(&mv("a5", "a9")));
+    assert!(moves.contains(&mv("a5", "a4")));
+    assert!(moves.contains(&mv("a5", "b5")));
+}
+
+#[test]
+fn test_two_square_scout_leaving_stretch() {
+    let mut board = two_square_board();
+    // The scout's second move leaves the stretch of the first, so it isn't
+    // shuffling between the same squares.
+    play_red(&mut board, &[("a8", "a6"), ("a6", "a9"), ("a9", "a6")]);
+    assert!(board.find_moves(Coord::from("a6").unwrap()).contains(&mv("a6", "a9")));
+}
+
+/// A loop of tiles around the left lake.
+const LAKE_LOOP: [&str; 12] = [
+    "b3", "c3", "d3", "e3", "e4", "e5", "e6", "d6", "c6", "b6", "b5", "b4",
+];
+
+/// Sets up a Red general about to chase a Blue sergeant around the left lake.
+fn chase_board() -> Board {
+    let mut board = Board::new();
+    board.set_tile(
+        Coord::from("b4").unwrap(),
+        Tile::Piece(Piece::General, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("c3").unwrap(),
+        Tile::Piece(Piece::Sergeant, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("j0").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Blue),
+    );
+    board
+}
+
+/// Chases the sergeant around the lake for the given range of moves.
+fn chase(board: &mut Board, moves: ::std::ops::Range<usize>) {
+    let sq = |k: usize| LAKE_LOOP[k % LAKE_LOOP.len()];
+    for k in moves {
+        board.try_move(mv(sq(k + 11), sq(k))).unwrap();
+        board.try_move(mv(sq(k + 1), sq(k + 2))).unwrap();
+    }
+}
+
+#[test]
+fn test_more_square_chase() {
+    use error::IllegalMove;
+
+    let mut board = chase_board();
+    chase(&mut board, 0 .. 12);
+
+    // Carrying on the chase would repeat the position after the first move.
+    let b4 = Coord::from("b4").unwrap();
+    assert!(!board.find_moves(b4).contains(&mv("b4", "b3")));
+    assert_eq!(
+        board.try_move(mv("b4", "b3")),
+        Err(IllegalMove::MoreSquareRule)
+    );
+    // Breaking off the chase is fine.
+    assert!(board.find_moves(b4).contains(&mv("b4", "a4")));
+}
+
+#[test]
+fn test_more_square_new_positions() {
+    let mut board = chase_board();
+    chase(&mut board, 0 .. 11);
+
+    // The chase hasn't yet come back round to a repeated position.
+    let b5 = Coord::from("b5").unwrap();
+    assert!(board.find_moves(b5).contains(&mv("b5", "b4")));
+}
+
+#[test]
+fn test_more_square_interrupted() {
+    let mut board = chase_board();
+    board.set_tile(
+        Coord::from("j9").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Red),
+    );
+    chase(&mut board, 0 .. 6);
+    // Both sides move other pieces, so the chase starts afresh.
+    board.try_move(mv("j9", "j8")).unwrap();
+    board.try_move(mv("j0", "j1")).unwrap();
+    chase(&mut board, 6 .. 12);
+
+    let b4 = Coord::from("b4").unwrap();
+    assert!(board.find_moves(b4).contains(&mv("b4", "b3")));
+}
+
+/// A board with both flags placed out of the way.
+fn flags_board() -> Board {
+    let mut board = Board::new();
+    board.set_tile(
+        Coord::from("j3").unwrap(),
+        Tile::Piece(Piece::Flag, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("j6").unwrap(),
+        Tile::Piece(Piece::Flag, Colour::Red),
+    );
+    board
+}
+
+#[test]
+fn test_draw_mutual_annihilation() {
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("e6").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("e5").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Blue),
+    );
+
+    board.try_move(mv("e6", "e5")).unwrap();
+    assert_eq!(
+        board.result(),
+        GameResult::Draw(DrawReason::MutualAnnihilation)
+    );
+}
+
+#[test]
+fn test_draw_no_moves() {
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("a9").unwrap(),
+        Tile::Piece(Piece::Bomb, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Bomb, Colour::Blue),
+    );
+    assert_eq!(board.result(), GameResult::Draw(DrawReason::NoMoves));
+}
+
+#[test]
+fn test_draw_move_limit() {
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("e8").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Blue),
+    );
+    board.set_move_limit(Some(4));
+
+    play_red(&mut board, &[("e8", "e7")]);
+    assert_eq!(board.result(), GameResult::Ongoing);
+    play_red(&mut board, &[("e7", "e6")]);
+    assert_eq!(board.result(), GameResult::Draw(DrawReason::MoveLimit));
+}
+
+#[test]
+fn test_draw_repetition() {
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("e8").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Blue),
+    );
+    let red_loop = [("e8", "f8"), ("f8", "f7"), ("f7", "e7"), ("e7", "e8")];
+
+    // Both sides walk around a loop, coming back to the starting position.
+    play_red(&mut board, &red_loop);
+    assert_eq!(board.result(), GameResult::Ongoing);
+    play_red(&mut board, &red_loop);
+    assert_eq!(board.result(), GameResult::Draw(DrawReason::Repetition));
+}
+
+/// A setup with the army in order, and the given pieces swapped into the
+/// given places.
+fn ordered_setup(swaps: &[((usize, usize), Piece)]) -> Setup {
+    let mut setup = Setup {rows: vec![vec![None; 10]; 4]};
+    for (n, piece) in ARMY.iter().enumerate() {
+        setup.rows[n / 10][n % 10] = Some(*piece);
+    }
+    for &((r, c), piece) in swaps {
+        let (pr, pc) = (0 .. 40)
+            .map(|n| (n / 10, n % 10))
+            .find(|&(pr, pc)| setup.rows[pr][pc] == Some(piece))
+            .unwrap();
+        setup.rows[pr][pc] = setup.rows[r][c];
+        setup.rows[r][c] = Some(piece);
+    }
+    setup
+}
+
+#[test]
+fn test_setup_orientation() {
+    let setup = ordered_setup(&[((0, 0), Piece::Flag), ((3, 9), Piece::Spy)]);
+    let mut board = Board::new();
+    board.place(Colour::Red, &setup);
+    board.place(Colour::Blue, &setup);
+
+    // Each player's back-left corner, from their side of the board.
+    assert_eq!(board.tile_at(Coord::from("a9").unwrap()), Tile::Piece(Piece::Flag, Colour::Red));
+    assert_eq!(board.tile_at(Coord::from("j0").unwrap()), Tile::Piece(Piece::Flag, Colour::Blue));
+    // Each player's front-right corner.
+    assert_eq!(board.tile_at(Coord::from("j6").unwrap()), Tile::Piece(Piece::Spy, Colour::Red));
+    assert_eq!(board.tile_at(Coord::from("a3").unwrap()), Tile::Piece(Piece::Spy, Colour::Blue));
+
+    assert_eq!(Setup::from_board(&board, Colour::Red), Some(setup.clone()));
+    assert_eq!(Setup::from_board(&board, Colour::Blue), Some(setup));
+}
+
+#[test]
+fn test_play_game_scripted() {
+    use player::{play_game, Scripted};
+
+    // Red's scout runs straight up the left edge into Blue's flag.
+    let red_setup = ordered_setup(&[((3, 0), Piece::Scout)]);
+    let blue_setup = ordered_setup(&[((3, 9), Piece::Flag)]);
+    let mut red = Scripted::new(red_setup, vec![mv("a6", "a3")]);
+    let mut blue = Scripted::new(blue_setup, vec![]);
+
+    let mut board = Board::new();
+    let result = play_game(&mut board, &mut red, &mut blue).unwrap();
+    assert_eq!(result, GameResult::Win(Colour::Red, WinReason::FlagCaptured));
+}
+
+#[test]
+fn test_play_game_scripted_errors() {
+    use error::{Error, IllegalMove};
+    use player::{play_game, Scripted};
+
+    let setup = ordered_setup(&[]);
+    let mut red = Scripted::new(setup.clone(), vec![mv("i6", "i4")]);
+    let mut blue = Scripted::new(setup.clone(), vec![]);
+    match play_game(&mut Board::new(), &mut red, &mut blue) {
+        Err(Error::IllegalMove(IllegalMove::NotAdjacent)) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    let mut red = Scripted::new(setup.clone(), vec![mv("a6", "a5")]);
+    let mut blue = Scripted::new(setup, vec![]);
+    match play_game(&mut Board::new(), &mut red, &mut blue) {
+        Err(Error::EarlyExit) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+}
+
+#[test]
+fn test_view_hides_enemy() {
+    use view::{Seen, View};
+
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("e6").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+
+    let red = View::new(&board, Colour::Red);
+    let blue = View::new(&board, Colour::Blue);
+    let e6 = Coord::from("e6").unwrap();
+    assert_eq!(
+        red.tile_at(e6),
+        Seen::Own {piece: Piece::Major, revealed: false, moved: false}
+    );
+    assert_eq!(blue.tile_at(e6), Seen::Enemy {rank: None, moved: false});
+    assert_eq!(blue.tile_at(Coord::from("c4").unwrap()), Seen::Terrain);
+    assert_eq!(blue.tile_at(Coord::from("e5").unwrap()), Seen::Empty);
+    assert_eq!(red.moves(), &board.find_all_moves(Colour::Red)[..]);
+}
+
+#[test]
+fn test_view_knowledge() {
+    use view::{Seen, View};
+
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("e8").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("a9").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("e5").unwrap(),
+        Tile::Piece(Piece::Captain, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("e4").unwrap(),
+        Tile::Piece(Piece::Colonel, Colour::Blue),
+    );
+
+    board.try_move(mv("e8", "e7")).unwrap();
+    board.try_move(mv("e5", "e6")).unwrap();
+    let blue = View::new(&board, Colour::Blue);
+    assert_eq!(
+        blue.tile_at(Coord::from("e7").unwrap()),
+        Seen::Enemy {rank: None, moved: true}
+    );
+    assert_eq!(
+        blue.tile_at(Coord::from("a9").unwrap()),
+        Seen::Enemy {rank: None, moved: false}
+    );
+
+    // The major takes the captain, and is revealed.
+    board.try_move(mv("e7", "e6")).unwrap();
+    let blue = View::new(&board, Colour::Blue);
+    let red = View::new(&board, Colour::Red);
+    assert_eq!(
+        blue.tile_at(Coord::from("e6").unwrap()),
+        Seen::Enemy {rank: Some(Piece::Major), moved: true}
+    );
+    assert_eq!(
+        red.tile_at(Coord::from("e6").unwrap()),
+        Seen::Own {piece: Piece::Major, revealed: true, moved: true}
+    );
+    assert_eq!(blue.own_captured, vec![Piece::Captain]);
+    assert_eq!(red.enemy_captured, vec![Piece::Captain]);
+
+    // The colonel takes the major, and is revealed in turn.
+    board.try_move(mv("e4", "e5")).unwrap();
+    board.try_move(mv("a9", "a8")).unwrap();
+    board.try_move(mv("e5", "e6")).unwrap();
+    let red = View::new(&board, Colour::Red);
+    assert_eq!(
+        red.tile_at(Coord::from("e6").unwrap()),
+        Seen::Enemy {rank: Some(Piece::Colonel), moved: true}
+    );
+    assert_eq!(red.own_captured, vec![Piece::Major]);
+}
+
+#[test]
+fn test_piece_info() {
+    let mut board = flags_board();
+    let e8 = Coord::from("e8").unwrap();
+    let e5 = Coord::from("e5").unwrap();
+    board.set_tile(e8, Tile::Piece(Piece::Captain, Colour::Red));
+    board.set_tile(e5, Tile::Piece(Piece::Major, Colour::Blue));
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Blue),
+    );
+    let captain = board.info_at(e8).unwrap();
+    let major = board.info_at(e5).unwrap();
+    assert!(captain.id != major.id);
+    assert!(!captain.revealed && !captain.moved);
+    assert_eq!(board.info_at(Coord::from("e7").unwrap()), None);
+
+    // Plain moves keep the piece's identity and mark it as moved.
+    board.try_move(mv("e8", "e7")).unwrap();
+    board.try_move(mv("a0", "a1")).unwrap();
+    board.try_move(mv("e7", "e6")).unwrap();
+    let info = board.info_at(Coord::from("e6").unwrap()).unwrap();
+    assert_eq!(info.id, captain.id);
+    assert!(info.moved && !info.revealed);
+
+    // The major wins its defence; it is revealed, but hasn't moved.
+    board.try_move(mv("a1", "a2")).unwrap();
+    board.try_move(mv("e6", "e5")).unwrap();
+    let info = board.info_at(e5).unwrap();
+    assert_eq!(info.id, major.id);
+    assert!(info.revealed && !info.moved);
+    assert_eq!(board.show(e5, Colour::Red), "4");
+    assert_eq!(board.show(Coord::from("a2").unwrap(), Colour::Red), "▇");
+}
+
+#[test]
+fn test_notation_squares() {
+    use error::NotationError;
+    use notation::{read_square, write_square, Files};
+
+    let a9 = Coord::from("a9").unwrap();
+    let j0 = Coord::from("j0").unwrap();
+    assert_eq!(write_square(a9, Files::AToJ, Geometry::CLASSIC), "A1");
+    assert_eq!(write_square(j0, Files::AToJ, Geometry::CLASSIC), "J10");
+    assert_eq!(write_square(j0, Files::AToK, Geometry::CLASSIC), "K10");
+    assert_eq!(read_square("A1", Files::AToJ, Geometry::CLASSIC), Ok(a9));
+    assert_eq!(read_square("j10", Files::AToJ, Geometry::CLASSIC), Ok(j0));
+    assert_eq!(read_square("K10", Files::AToK, Geometry::CLASSIC), Ok(j0));
+    assert_eq!(
+        read_square("I5", Files::AToK, Geometry::CLASSIC),
+        Err(NotationError::Square("I5".to_string()))
+    );
+    assert!(read_square("A11", Files::AToJ, Geometry::CLASSIC).is_err());
+    assert!(read_square("A0", Files::AToJ, Geometry::CLASSIC).is_err());
+    assert!(read_square("K1", Files::AToJ, Geometry::CLASSIC).is_err());
+    assert!(read_square("", Files::AToJ, Geometry::CLASSIC).is_err());
+}
+
+#[test]
+fn test_notation_moves() {
+    use notation::{read_move, write_move, Files, Notated};
+    use rules::Numbering;
+
+    let mut board = flags_board();
+    board.set_tile(
+        Coord::from("b8").unwrap(),
+        Tile::Piece(Piece::Major, Colour::Red),
+    );
+    board.set_tile(
+        Coord::from("b6").unwrap(),
+        Tile::Piece(Piece::Captain, Colour::Blue),
+    );
+    board.set_tile(
+        Coord::from("a0").unwrap(),
+        Tile::Piece(Piece::Scout, Colour::Blue),
+    );
+
+    let outcome = board.try_move(mv("b8", "b7")).unwrap();
+    assert_eq!(write_move(&Notated::from(&outcome), Files::AToJ, Geometry::CLASSIC), "B2-B3");
+    board.try_move(mv("a0", "a1")).unwrap();
+    let outcome = board.try_move(mv("b7", "b6")).unwrap();
+    let written = write_move(&Notated::from(&outcome), Files::AToJ, Geometry::CLASSIC);
+    assert_eq!(written, "major B3xcaptain B4");
+
+    assert_eq!(
+        read_move(&written, Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Ok(Notated {
+            mv:       mv("b7", "b6"),
+            strike:   true,
+            attacker: Some(Piece::Major),
+            defender: Some(Piece::Captain),
+        })
+    );
+    assert_eq!(
+        read_move("4 B3x5 B4", Files::AToJ, Geometry::CLASSIC, Numbering::Classic).unwrap().defender,
+        Some(Piece::Captain)
+    );
+    let modern = read_move("10 B3x1 B4", Files::AToJ, Geometry::CLASSIC, Numbering::Modern).unwrap();
+    assert_eq!((modern.attacker, modern.defender), (Some(Piece::Marshall), Some(Piece::Spy)));
+    assert_eq!(
+        read_move("7 B3xcaptain B4", Files::AToJ, Geometry::CLASSIC, Numbering::Modern).unwrap().attacker,
+        Some(Piece::Major)
+    );
+    assert_eq!(
+        read_move("X B3Xcaptain B4", Files::AToJ, Geometry::CLASSIC, Numbering::Modern),
+        Ok(Notated {
+            mv:       mv("b7", "b6"),
+            strike:   true,
+            attacker: Some(Piece::Marshall),
+            defender: Some(Piece::Captain),
+        })
+    );
+    assert_eq!(
+        read_move("x B3xX B4", Files::AToJ, Geometry::CLASSIC, Numbering::Modern).unwrap().defender,
+        Some(Piece::Marshall)
+    );
+    assert_eq!(
+        read_move("B2-B3", Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Ok(Notated {
+            mv:       mv("b8", "b7"),
+            strike:   false,
+            attacker: None,
+            defender: None,
+        })
+    );
+}
+
+#[test]
+fn test_notation_errors() {
+    use error::NotationError::*;
+    use notation::{read_move, Files};
+    use rules::Numbering;
+
+    assert_eq!(
+        read_move("B2B3", Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Err(Separator("B2B3".to_string()))
+    );
+    assert_eq!(
+        read_move("general B2-B3", Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Err(RankWithoutStrike("general B2-B3".to_string()))
+    );
+    assert_eq!(
+        read_move("admiral B2xB3", Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Err(Rank("admiral".to_string()))
+    );
+    assert_eq!(
+        read_move("B2-Z3", Files::AToJ, Geometry::CLASSIC, Numbering::Classic),
+        Err(Square("Z3".to_string()))
+    );
+}
+
+/// A finished game, where Red's scout runs up the left edge into the flag.
+fn recorded_game() -> Board {
+    use player::{play_game, Scripted};
+
+    let red_setup = ordered_setup(&[((3, 0), Piece::Scout)]);
+    let blue_setup = ordered_setup(&[((3, 9), Piece::Flag)]);
+    let mut red = Scripted::new(red_setup, vec![mv("a6", "a3")]);
+    let mut blue = Scripted::new(blue_setup, vec![]);
+    let mut board = Board::new();
+    play_game(&mut board, &mut red, &mut blue).unwrap();
+    board
+}
+
+#[test]
+fn test_record_round_trip() {
+    use record::Record;
+
+    let board = recorded_game();
+    let record = Record::from_board(&board, "scripted", "scripted").unwrap();
+    let written = record.to_string();
+    assert!(written.contains("[Result \"red\"]"));
+    assert!(written.contains("[Termination \"flag captured\"]"));
+    assert!(written.contains("1. scout A4xflag A7"));
+
+    let read = Record::read(&written).unwrap();
+    assert_eq!(read, record);
+
+    let start = read.board_at(0).unwrap();
+    assert_eq!(start.tile_at(Coord::from("a6").unwrap()), Tile::Piece(Piece::Scout, Colour::Red));
+    assert_eq!(start.result(), GameResult::Ongoing);
+    let end = read.board_at(read.moves.len()).unwrap();
+    assert_eq!(end.result(), record.result);
+    assert_eq!(end.history().len(), 1);
+}
+
+#[test]
+fn test_record_errors() {
+    use error::{IllegalMove, RecordError};
+    use record::Record;
+
+    let board = recorded_game();
+    let record = Record::from_board(&board, "scripted", "scripted").unwrap();
+
+    let mut wrong = record.clone();
+    wrong.moves[0].defender = Some(Piece::Bomb);
+    assert_eq!(wrong.board_at(1).err(), Some(RecordError::Mismatch(1)));
+
+    let mut wrong = record.clone();
+    wrong.moves[0].mv = mv("a6", "b5");
+    wrong.moves[0].strike = false;
+    assert_eq!(
+        wrong.board_at(1).err(),
+        Some(RecordError::IllegalMove(1, IllegalMove::InvalidScoutPath))
+    );
+
+    let written = record.to_string().replace("[Date", "[Day");
+    assert_eq!(
+        Record::read(&written).err(),
+        Some(RecordError::MissingTag("Date".to_string()))
+    );
+    let written = record.to_string() + "2. A4-A5-A6\n";
+    assert_eq!(
+        Record::read(&written).err(),
+        Some(RecordError::InvalidMove(
+            2,
+            ::error::NotationError::Square("A5-A6".to_string())
+        ))
+    );
+}
+
+#[test]
+fn test_gravon_round_trip() {
+    use gravon::{self, Game};
+    use record::Record;
+
+    let board = recorded_game();
+    let record = Record::from_board(&board, "scripted", "scripted").unwrap();
+    let game = Game::from(&record);
+    let xml = game.to_string();
+    assert!(xml.contains("<move id=\"1\" source=\"A4\" target=\"A7\"/>"));
+    assert!(xml.contains("<result type=\"1\" winner=\"0\"/>"));
+    assert_eq!(Game::read(&xml), Ok(game.clone()));
+
+    let field = gravon::write_field(&board.initial());
+    assert_eq!(field.len(), 100);
+    assert_eq!(&field[40 .. 60], "AA__AA__AAAA__AA__AA");
+    // Red's back-left corner is A1, and Blue's is K10.
+    assert_eq!(&field[.. 1], "M");
+    assert_eq!(&field[99 ..], "Y");
+    assert_eq!(gravon::write_field(&gravon::read_field(&field).unwrap()), field);
+
+    let end = game.board_at(game.moves.len()).unwrap();
+    assert_eq!(end.result(), GameResult::Win(Colour::Red, WinReason::FlagCaptured));
+}
+
+#[test]
+fn test_gravon_read() {
+    use error::{GravonError, IllegalMove};
+    use gravon::{self, Game};
+
+    let field = gravon::write_field(&recorded_game().initial());
+    let xml = format!(
+        "<stratego><game>\n\
+         <player id='0' name='someone'/>\n\
+         <field content='{}' />\n\
+         <moves><move id='1' source='b4' target='B5'/></moves>\n\
+         <result type='3' winner='1'/>\n\
+         </game></stratego>",
+        field
+    );
+    let game = Game::read(&xml).unwrap();
+    assert_eq!(game.moves, vec![mv("b6", "b5")]);
+    assert_eq!(game.winner, Some(Colour::Blue));
+    assert_eq!(game.reason, None);
+
+    let illegal = xml.replace("target='B5'", "target='C5'");
+    assert_eq!(
+        Game::read(&illegal).unwrap().board_at(1).err(),
+        Some(GravonError::IllegalMove(1, IllegalMove::IntoTerrain))
+    );
+    let lake = field.replacen("AA__", "AAA_", 1);
+    assert_eq!(
+        gravon::read_field(&lake).err(),
+        Some(GravonError::InvalidField(lake.clone()))
+    );
+    assert_eq!(
+        Game::read("<game></game>"),
+        Err(GravonError::MissingElement("field".to_string()))
+    );
+}
+
+#[test]
+fn test_setup_strings() {
+    use error::SetupError;
+
+    let setup = Setup::default();
+    let written = setup.to_string();
+    assert_eq!(written, "5879896958949FS7458968BB2B7B3973B4561B96");
+    assert_eq!(Setup::read(&written), Ok(setup.clone()));
+    assert_eq!(Setup::read("58798 96958\n949fs 74589\n68bb2 b7b39\n73b45 61b96"), Ok(setup.clone()));
+
+    let mut board = Board::new();
+    board.place(Colour::Blue, &setup);
+    assert_eq!(board.tile_at(Coord::from("g1").unwrap()), Tile::Piece(Piece::Flag, Colour::Blue));
+    assert_eq!(board.tile_at(Coord::from("d3").unwrap()), Tile::Piece(Piece::Marshall, Colour::Blue));
+
+    assert_eq!(Setup::read(&written[1 ..]), Err(SetupError::Length(39)));
+    assert_eq!(Setup::read(&written.replace('F', "X")), Err(SetupError::Symbol('X')));
+    assert_eq!(
+        Setup::read(&written.replace('F', "B")),
+        Err(SetupError::Army(Piece::Bomb, 7, 6))
+    );
+}
+
+#[test]
+fn test_library() {
+    use error::{Error, LibraryError};
+    use library::Library;
+
+    let dir = ::std::env::temp_dir().join(format!("stratagem-library-{}", ::std::process::id()));
+    let path = dir.join("setups");
+    let _ = ::std::fs::remove_dir_all(&dir);
+
+    let mut library = Library::open(&path).unwrap();
+    let built_in = library.presets().len();
+    assert!(library.presets().iter().all(|p| p.built_in));
+    assert_eq!(library.presets()[0].setup, Setup::default());
+
+    let setup = ordered_setup(&[]);
+    library.save("in order", setup.clone()).unwrap();
+    library.save("  spare ", Setup::default()).unwrap();
+    match library.save("default", setup.clone()) {
+        Err(Error::Library(LibraryError::BuiltIn(_))) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    let mut reopened = Library::open(&path).unwrap();
+    assert_eq!(reopened.presets(), library.presets());
+    assert_eq!(reopened.presets()[built_in].name, "in order");
+    assert_eq!(reopened.presets()[built_in].setup, setup);
+    assert!(reopened.delete("spare").unwrap());
+    assert!(!reopened.delete("spare").unwrap());
+    assert!(reopened.delete("default").is_err());
+    assert_eq!(Library::open(&path).unwrap().presets().len(), built_in + 1);
+
+    ::std::fs::write(&path, "not a setup\n").unwrap();
+    match Library::open(&path) {
+        Err(Error::Library(LibraryError::InvalidLine(1, _))) => (),
+        r => panic!("Unexpected result {:?}", r),
+    }
+    ::std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_seeded_randomness() {
+    use random;
+    use record::Record;
+    use rules::Rules;
+
+    let setup = Setup::random(Rules::default(), &mut random::seeded(42));
+    assert_eq!(Setup::random(Rules::default(), &mut random::seeded(42)), setup);
+    assert_ne!(Setup::random(Rules::default(), &mut random::seeded(43)), setup);
+    assert_eq!(Setup::read(&setup.to_string()), Ok(setup));
+
+    let mut rng = random::seeded(7);
+    let mut board = Board::new();
+    board.randomise(Colour::Red, &mut rng);
+    board.randomise(Colour::Blue, &mut rng);
+    let mut record = Record::from_board(&board, "a", "b").unwrap();
+    assert_eq!(record.seed, None);
+    record.seed = Some(7);
+    let written = record.to_string();
+    assert!(written.contains("[Seed \"7\"]"));
+    assert_eq!(Record::read(&written), Ok(record.clone()));
+
+    // Replaying the seed gives the same game.
+    let mut rng = random::seeded(7);
+    assert_eq!(Setup::random(Rules::default(), &mut rng), record.red_setup);
+    assert_eq!(Setup::random(Rules::default(), &mut rng), record.blue_setup);
+}
+
+#[test]
+fn test_rules_numbering() {
+    use rules::Numbering;
+
+    assert_eq!(Piece::Marshall.symbol(Numbering::Classic), "1");
+    assert_eq!(Piece::Marshall.symbol(Numbering::Modern), "X");
+    assert_eq!(Piece::Spy.symbol(Numbering::Classic), "S");
+    assert_eq!(Piece::Spy.symbol(Numbering::Modern), "1");
+    assert_eq!(Piece::from_symbol("10", Numbering::Modern), Some(Piece::Marshall));
+    assert_eq!(Piece::from_symbol("1", Numbering::Modern), Some(Piece::Spy));
+    assert_eq!(Piece::from_symbol("1", Numbering::Classic), Some(Piece::Marshall));
+    assert_eq!(Piece::from_symbol("10", Numbering::Classic), None);
+    for &numbering in &[Numbering::Classic, Numbering::Modern] {
+        for &piece in ARMY.iter() {
+            assert_eq!(Piece::from_symbol(piece.symbol(numbering), numbering), Some(piece));
+        }
+    }
+}
+
+#[test]
+fn test_rules_scout_strikes() {
+    use error::IllegalMove;
+    use rules::Rules;
+
+    let rules = Rules { scout_strikes: false, ..Rules::default() };
+    let mut board = Board::with_rules(rules);
+    let c0 = Coord::from("c0").unwrap();
+    let f0 = Coord::from("f0").unwrap();
+    board.set_tile(c0, Tile::Piece(Piece::Scout, Colour::Red));
+    board.set_tile(f0, Tile::Piece(Piece::Miner, Colour::Blue));
+
+    assert!(!board.find_moves(c0).contains(&Move::new(c0, f0)));
+    assert!(board.find_moves(c0).contains(&mv("c0", "e0")));
+    assert_eq!(board.try_move(Move::new(c0, f0)), Err(IllegalMove::ScoutStrike));
+
+    // Striking an adjacent piece is still allowed.
+    board.try_move(mv("c0", "e0")).unwrap();
+    board.try_move(mv("f0", "f1")).unwrap();
+    board.set_tile(Coord::from("f0").unwrap(), Tile::Piece(Piece::Spy, Colour::Blue));
+    assert!(board.try_move(mv("e0", "f0")).unwrap().strike.is_some());
+}
+
+#[test]
+fn test_rules_defender_advances() {
+    use record::Record;
+    use rules::Rules;
+
+    let rules = Rules { defender_advances: true, ..Rules::default() };
+    let e0 = Coord::from("e0").unwrap();
+    let e1 = Coord::from("e1").unwrap();
+
+    let mut board = Board::with_rules(rules);
+    board.set_tile(e0, Tile::Piece(Piece::Major, Colour::Red));
+    board.set_tile(e1, Tile::Piece(Piece::Colonel, Colour::Blue));
+    let outcome = board.try_move(Move::new(e0, e1)).unwrap();
+    assert!(outcome.advanced);
+    assert_eq!(outcome.survivor(), Some(e0));
+    assert_eq!(
+        outcome.revealed(),
+        vec![(e0, Piece::Major, Colour::Red), (e0, Piece::Colonel, Colour::Blue)]
+    );
+    assert_eq!(board.tile_at(e0), Tile::Piece(Piece::Colonel, Colour::Blue));
+    assert_eq!(board.tile_at(e1), Tile::Empty);
+    assert!(board.info_at(e0).unwrap().moved);
+
+    // Bombs never move.
+    let mut board = Board::with_rules(rules);
+    board.set_tile(e0, Tile::Piece(Piece::Major, Colour::Red));
+    board.set_tile(e1, Tile::Piece(Piece::Bomb, Colour::Blue));
+    let outcome = board.try_move(Move::new(e0, e1)).unwrap();
+    assert!(!outcome.advanced);
+    assert_eq!(board.tile_at(e1), Tile::Piece(Piece::Bomb, Colour::Blue));
+
+    // Records keep the rules of their variant.
+    assert_eq!(rules.variant(), "classic defender-advances");
+    assert_eq!(Rules::from_variant(&rules.variant()), Some(rules));
+    assert_eq!(Rules::from_variant("classic"), Some(Rules::default()));
+    assert_eq!(Rules::from_variant("chess"), None);
+
+    let mut board = Board::with_rules(rules);
+    board.place(Colour::Red, &ordered_setup(&[]));
+    board.place(Colour::Blue, &ordered_setup(&[]));
+    board.try_move(mv("a6", "a5")).unwrap();
+    let record = Record::from_board(&board, "a", "b").unwrap();
+    assert_eq!(record.variant, "classic defender-advances");
+    assert_eq!(record.board_at(1).unwrap().rules(), rules);
+}
+
+#[test]
+fn test_barrage() {
+    use error::SetupError;
+    use library::Library;
+    use random;
+    use record::Record;
+    use rules::Rules;
+
+    let written = "...BF8........2......S........9..1....9.";
+    let setup = Setup::read(written).unwrap();
+    assert_eq!(setup.to_string(), written);
+    assert_eq!(setup.army(), Some(Army::Barrage));
+    assert_eq!(Setup::default().army(), Some(Army::Classic));
+    assert_eq!(Setup::read(&written.replace('B', ".")), Err(SetupError::Count(7)));
+    assert_eq!(
+        Setup::read(&written.replace('8', "7")),
+        Err(SetupError::Army(Piece::Miner, 0, 1))
+    );
+
+    let rules = Rules { army: Army::Barrage, ..Rules::default() };
+    let random = Setup::random(rules, &mut random::seeded(1));
+    assert_eq!(random.army(), Some(Army::Barrage));
+    assert_eq!(random.pieces().count(), 8);
+    assert_eq!(rules.variant(), "barrage");
+    assert_eq!(Rules::from_variant("barrage no-scout-strikes").map(|r| r.army), Some(Army::Barrage));
+
+    // Only barrage presets suit a game of Barrage.
+    let library = Library::built_in();
+    assert!(library.presets().iter().any(|p| p.setup.army() == Some(Army::Barrage)));
+
+    // Records only accept setups of the variant's army.
+    let mut board = Board::with_rules(rules);
+    board.place(Colour::Red, &setup);
+    board.place(Colour::Blue, &Setup::default());
+    assert_eq!(Record::from_board(&board, "a", "b"), None);
+
+    let mut board = Board::with_rules(rules);
+    board.place(Colour::Red, &setup);
+    board.place(Colour::Blue, &setup);
+    let record = Record::from_board(&board, "a", "b").unwrap();
+    assert_eq!(record.variant, "barrage");
+    let replayed = Record::read(&record.to_string()).unwrap().board_at(0).unwrap();
+    assert_eq!(replayed.rules(), rules);
+    assert_eq!(Setup::from_board(&replayed, Colour::Red), Some(setup));
+
+    // With so few pieces, losing the last movable one ends the game.
+    assert_eq!(board.result(), GameResult::Ongoing);
+    let mut board = Board::with_rules(rules);
+    board.set_tile(Coord::from("a9").unwrap(), Tile::Piece(Piece::Flag, Colour::Red));
+    board.set_tile(Coord::from("e6").unwrap(), Tile::Piece(Piece::General, Colour::Red));
+    board.set_tile(Coord::from("a0").unwrap(), Tile::Piece(Piece::Flag, Colour::Blue));
+    board.set_tile(Coord::from("e5").unwrap(), Tile::Piece(Piece::Miner, Colour::Blue));
+    board.try_move(mv("e6", "e5")).unwrap();
+    assert_eq!(board.result(), GameResult::Win(Colour::Red, WinReason::NoMoves));
+}
+
+#[test]
+fn test_board_geometry() {
+    use error::IllegalMove;
+    use gravon::{self, Game};
+    use library::Library;
+    use notation::{read_square, write_square, Files};
+    use record::Record;
+    use rules::Rules;
+
+    let rules = Rules { army: Army::Barrage, geometry: Geometry::DUEL, ..Rules::default() };
+    assert_eq!(rules.variant(), "barrage duel");
+    assert_eq!(Rules::from_variant("barrage duel"), Some(rules));
+    // The classic army doesn't fit on the duel board.
+    assert_eq!(Rules::from_variant("classic duel"), None);
+
+    let h8 = Coord {x: 7, y: 0};
+    let a1 = Coord {x: 0, y: 7};
+    assert_eq!(Coord::parse("h0", Geometry::DUEL), Some(h8));
+    assert_eq!(Coord::parse("a7", Geometry::DUEL), Some(a1));
+    assert_eq!(Coord::parse("i0", Geometry::DUEL), None);
+    assert_eq!(Coord::parse("j9", Geometry::DUEL), None);
+    assert_eq!(Coord::from("j9"), Some(Coord {x: 9, y: 9}));
+    assert_eq!(write_square(a1, Files::AToJ, Geometry::DUEL), "A1");
+    assert_eq!(write_square(h8, Files::AToJ, Geometry::DUEL), "H8");
+    assert_eq!(read_square("h8", Files::AToJ, Geometry::DUEL), Ok(h8));
+    assert!(read_square("A9", Files::AToJ, Geometry::DUEL).is_err());
+    assert!(read_square("J1", Files::AToJ, Geometry::DUEL).is_err());
+
+    let library = Library::built_in();
+    let setup = library
+        .presets()
+        .iter()
+        .find(|p| rules.allows(&p.setup))
+        .unwrap()
+        .setup
+        .clone();
+    assert_eq!(setup.geometry(), Some(Geometry::DUEL));
+    assert_eq!(Setup::read(&setup.to_string()), Ok(setup.clone()));
+    assert_eq!(Setup::random(rules, &mut ::random::seeded(3)).geometry(), Some(Geometry::DUEL));
+
+    let mut board = Board::with_rules(rules);
+    board.place(Colour::Red, &setup);
+    board.place(Colour::Blue, &setup);
+    assert_eq!(board.tile_at(Coord {x: 2, y: 3}), Tile::Terrain);
+    assert_eq!(board.tile_at(Coord {x: 3, y: 3}), Tile::Empty);
+    assert_eq!(board.tile_at(Coord {x: 1, y: 7}), Tile::Piece(Piece::Miner, Colour::Red));
+    assert_eq!(Setup::from_board(&board, Colour::Blue), Some(setup.clone()));
+    assert!(board.display_to(Colour::Red).unwrap().starts_with("┌────────────────────────┐"));
+
+    // Pieces stay on the board, and scouts stop at its edge.
+    assert_eq!(
+        board.try_move(Move::new(Coord {x: 0, y: 5}, Coord {x: 8, y: 5})),
+        Err(IllegalMove::OutOfBounds)
+    );
+    let scout = Coord {x: 0, y: 5};
+    assert_eq!(board.tile_at(scout), Tile::Piece(Piece::Scout, Colour::Red));
+    assert!(board.find_moves(scout).contains(&Move::new(scout, Coord {x: 0, y: 0})));
+    assert!(board.find_moves(scout).iter().all(|m| board.geometry().contains(m.to)));
+    board.try_move(Move::new(scout, Coord {x: 0, y: 3})).unwrap();
+
+    let record = Record::from_board(&board, "a", "b").unwrap();
+    assert_eq!(record.variant, "barrage duel");
+    let written = record.to_string();
+    assert!(written.contains("1. A3-A5"));
+    let read = Record::read(&written).unwrap();
+    assert_eq!(read, record);
+    assert_eq!(read.board_at(1).unwrap().geometry(), Geometry::DUEL);
+
+    let game = Game::from(&record);
+    let xml = game.to_string();
+    let field = gravon::write_field(&board.initial());
+    assert_eq!(field.len(), 64);
+    assert_eq!(gravon::read_field(&field).unwrap().geometry(), Geometry::DUEL);
+    assert_eq!(Game::read(&xml), Ok(game.clone()));
+    assert_eq!(game.board_at(1).unwrap().rules(), rules);
+}
+
+#[test]
+fn test_random_bot() {
+    use bot::Random;
+    use player::{play_game, Player};
+    use rules::Rules;
+    use view::View;
+
+    let play = |seed: u64, rules: Rules| {
+        let mut board = Board::with_rules(rules);
+        board.set_move_limit(Some(400));
+        let result = play_game(&mut board, &mut Random::new(seed), &mut Random::new(seed + 1))
+            .unwrap();
+        (result, board.history().to_vec())
+    };
+
+    // Every move is legal, or the game would have failed, and the same
+    // seeds play the same game.
+    let (result, history) = play(1, Rules::default());
+    assert_ne!(result, GameResult::Ongoing);
+    assert_eq!(play(1, Rules::default()), (result, history.clone()));
+    assert_ne!(play(2, Rules::default()).1, history);
+
+    let rules = Rules { army: Army::Barrage, geometry: Geometry::DUEL, ..Rules::default() };
+    assert_ne!(play(1, rules).0, GameResult::Ongoing);
+
+    // Moves are picked from every piece that can move.
+    let mut board = Board::new();
+    let mut bot = Random::new(5);
+    board.place(Colour::Red, &bot.setup(Colour::Red, board.rules()).unwrap());
+    let view = View::new(&board, Colour::Red);
+    let mut pieces = (0 .. 200)
+        .map(|_| bot.choose_move(&view).unwrap().from)
+        .collect::<Vec<_>>();
+    assert!(pieces.iter().all(|c| view.moves().iter().any(|m| m.from == *c)));
+    let mut movable = view.moves().iter().map(|m| m.from).collect::<Vec<_>>();
+    for list in &mut [&mut pieces, &mut movable] {
+        list.sort_by_key(|c| (c.y, c.x));
+        list.dedup();
+    }
+    assert_eq!(pieces, movable);
+}
+
+/// A board where a blue general has just struck a red scout, revealing itself
+/// next to the red marshall, which can take it.
+fn general_strike_board() -> Board {
+    let mut board = Board::new();
+    for &(c, piece, colour) in &[
+        ("a9", Piece::Flag, Colour::Red),
+        ("a6", Piece::Sergeant, Colour::Red),
+        ("e7", Piece::Marshall, Colour::Red),
+        ("e6", Piece::Scout, Colour::Red),
+        ("j0", Piece::Flag, Colour::Blue),
+        ("j3", Piece::Sergeant, Colour::Blue),
+        ("e5", Piece::General, Colour::Blue),
+    ] {
+        board.set_tile(Coord::from(c).unwrap(), Tile::Piece(piece, colour));
+    }
+    board.try_move(mv("a6", "a5")).unwrap();
+    board.try_move(mv("e5", "e6")).unwrap();
+    board
+}
+
+/// How many games the player wins as Blue against one moving at random, with
+/// Barrage armies on the duel board, seeding both with each game's number.
+fn wins_against_random<P, F>(player: F, games: u64) -> usize
+where
+    P: ::player::Player,
+    F: Fn(u64) -> P,
+{
+    use bot::Random;
+    use player::play_game;
+    use rules::Rules;
+
+    let rules = Rules { army: Army::Barrage, geometry: Geometry::DUEL, ..Rules::default() };
+    (0 .. games)
+        .filter(|&seed| {
+            let mut board = Board::with_rules(rules);
+            board.set_move_limit(Some(400));
+            let result = play_game(&mut board, &mut Random::new(seed), &mut player(seed)).unwrap();
+            matches!(result, GameResult::Win(Colour::Blue, _))
+        })
+        .count()
+}
+
+#[test]
+fn test_heuristic_bot() {
+    use bot::{Heuristic, Level, Random};
+    use player::{play_game, Player};
+    use rules::Rules;
+    use view::View;
+
+    let rules = Rules::default();
+    let setup = Heuristic::new(Level::Hard, 1).setup(Colour::Red, rules).unwrap();
+    assert!(rules.allows(&setup));
+    let flag = setup.rows[0].iter().position(|&p| p == Some(Piece::Flag)).unwrap();
+    assert_eq!(setup.rows[1][flag], Some(Piece::Bomb));
+
+    // The blue general is also next to the red captain.
+    let mut board = general_strike_board();
+    board.set_tile(
+        Coord::from("f6").unwrap(),
+        Tile::Piece(Piece::Captain, Colour::Red),
+    );
+
+    // The marshall takes the general, rather than the captain losing to it.
+    let view = View::new(&board, Colour::Red);
+    for &level in &Level::ALL[1 ..] {
+        assert_eq!(Heuristic::new(level, 2).choose_move(&view).unwrap(), mv("e7", "e6"));
+    }
+
+    // Without the marshall, the captain gets out of the general's way.
+    board.set_tile(Coord::from("e7").unwrap(), Tile::Empty);
+    let view = View::new(&board, Colour::Red);
+    let m = Heuristic::new(Level::Hard, 3).choose_move(&view).unwrap();
+    assert_eq!(m.from, Coord::from("f6").unwrap());
+    assert_ne!(m.to, Coord::from("e6").unwrap());
+
+    // A hard player beats one playing at random.
+    let mut wins = 0;
+    for seed in 0 .. 4 {
+        let mut board = Board::new();
+        board.set_move_limit(Some(2000));
+        let result = play_game(
+            &mut board,
+            &mut Heuristic::new(Level::Hard, seed),
+            &mut Random::new(seed),
+        ).unwrap();
+        if let GameResult::Win(Colour::Red, _) = result {
+            wins += 1;
+        }
+    }
+    assert!(wins >= 3, "won {} of 4", wins);
+}
+
+#[test]
+fn test_belief() {
+    use belief::Belief;
+    use rules::Rules;
+    use view::View;
+
+    let mut board = Board::new();
+    board.place(Colour::Red, &ordered_setup(&[]));
+    board.place(Colour::Blue, &ordered_setup(&[]));
+    let mut belief = Belief::new(Colour::Red, Rules::default());
+    let c = |x, y| Coord {x, y};
+
+    // Before any move, every hidden piece is as likely as any other to be
+    // each rank.
+    belief.update(&View::new(&board, Colour::Red));
+    assert!((belief.chance(c(5, 0), Piece::Bomb) - 6.0 / 40.0).abs() < 1e-9);
+    assert!((belief.chance(c(0, 3), Piece::Flag) - 1.0 / 40.0).abs() < 1e-9);
+    assert_eq!(belief.likeliest(c(5, 0)), Some(Piece::Scout));
+    assert_eq!(belief.odds(c(5, 6)), vec![]);
+
+    let play = |board: &mut Board, belief: &mut Belief, from, to| {
+        let outcome = board.try_move(Move::new(from, to)).unwrap();
+        belief.observe(outcome.mv, outcome.strike);
+    };
+    play(&mut board, &mut belief, c(0, 6), c(0, 5));
+    play(&mut board, &mut belief, c(9, 3), c(9, 5));
+    // Red's scout strikes Blue's, and both are lost.
+    play(&mut board, &mut belief, c(4, 6), c(4, 3));
+    play(&mut board, &mut belief, c(8, 3), c(8, 4));
+    belief.update(&View::new(&board, Colour::Red));
+
+    // A piece that moved two tiles at once is a scout, and one that moved at
+    // all can't be a bomb or the flag.
+    assert_eq!(belief.odds(c(9, 5)), vec![(Piece::Scout, 1.0)]);
+    assert_eq!(belief.chance(c(8, 4), Piece::Bomb), 0.0);
+    assert_eq!(belief.chance(c(8, 4), Piece::Flag), 0.0);
+    assert!(belief.chance(c(8, 4), Piece::Scout) > belief.chance(c(5, 0), Piece::Scout));
+
+    // The captured scout is no longer shared between the hidden pieces.
+    let blue = board.geometry()
+        .coords()
+        .filter(|&t| matches!(board.tile_at(t), Tile::Piece(_, Colour::Blue)))
+        .collect::<Vec<_>>();
+    assert_eq!(blue.len(), 39);
+    let scouts = blue.iter().map(|&t| belief.chance(t, Piece::Scout)).sum::<f64>();
+    assert!((scouts - 7.0).abs() < 1e-6, "{} scouts", scouts);
+    for &t in &blue {
+        let total = belief.odds(t).iter().map(|&(_, p)| p).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-6);
+    }
+
+    // A piece that wins a battle is known for certain.
+    play(&mut board, &mut belief, c(0, 5), c(0, 4));
+    play(&mut board, &mut belief, c(8, 4), c(8, 5));
+    play(&mut board, &mut belief, c(0, 4), c(0, 5));
+    play(&mut board, &mut belief, c(8, 5), c(8, 6));
+    let view = View::new(&board, Colour::Red);
+    belief.update(&view);
+    assert_eq!(belief.odds(c(8, 6)), vec![(Piece::Scout, 1.0)]);
+
+    // Guesses at the hidden pieces make up the rest of the army, and never
+    // go against what is known.
+    let mut rng = ::random::seeded(1);
+    for _ in 0 .. 10 {
+        let guess = belief.sample(&view, &mut rng);
+        assert_eq!(guess.len(), 38);
+        let mut pieces = guess.iter().map(|&(_, p)| p).collect::<Vec<_>>();
+        pieces.extend(view.enemy_captured.iter().cloned());
+        pieces.push(Piece::Scout);
+        pieces.sort();
+        let mut army = ARMY.to_vec();
+        army.sort();
+        assert_eq!(pieces, army);
+        assert!(guess.contains(&(c(9, 5), Piece::Scout)));
+        assert!(guess.iter().all(|&(t, p)| belief.chance(t, p) > 0.0));
+    }
+}
+
+#[test]
+fn test_ismcts() {
+    use bot::Budget;
+    use ismcts::Ismcts;
+    use player::Player;
+    use std::time::Duration;
+    use view::View;
+
+    assert_eq!(Budget::from("500"), Some(Budget::Iterations(500)));
+    assert_eq!(Budget::from("3ply"), Some(Budget::Depth(3)));
+    assert_eq!(Budget::from("250ms"), Some(Budget::Time(Duration::from_millis(250))));
+    assert_eq!(Budget::from("fast"), None);
+    assert_eq!(Budget::Time(Duration::from_millis(250)).to_string(), "250ms");
+    assert_eq!(Budget::Depth(3).to_string(), "3ply");
+    assert!(Ismcts::new(Budget::Depth(3), 1).is_err());
+    assert!(::bot::named("ismcts", 1, None).unwrap().is_some());
+    assert!(::bot::named("expectimax", 1, Some(Budget::Iterations(500))).is_err());
+    assert!(::bot::named("deep blue", 1, None).unwrap().is_none());
+
+    // Boards set up partway through a game can start with either colour.
+    let mut board = Board::new();
+    board.set_to_move(Colour::Blue);
+    board.set_to_move(Colour::Blue);
+    assert_eq!(board.to_move(), Colour::Blue);
+    assert_eq!(board.initial().to_move(), Colour::Blue);
+
+    // The red marshall takes the blue general.
+    let board = general_strike_board();
+    let view = View::new(&board, Colour::Red);
+    let choose = |seed| Ismcts::new(Budget::Iterations(200), seed).unwrap().choose_move(&view).unwrap();
+    assert_eq!(choose(1), mv("e7", "e6"));
+    assert_eq!(choose(2), choose(2));
+
+    // It beats a player moving at random.
+    assert_eq!(wins_against_random(|seed| Ismcts::new(Budget::Iterations(100), seed).unwrap(), 2), 2);
+}
+
+#[test]
+fn test_expectimax() {
+    use belief::Belief;
+    use bot::Budget;
+    use error::BotError;
+    use expectimax::{Evaluation, Expectimax, Material, Position, Weighted};
+    use player::Player;
+    use rules::Rules;
+    use std::time::Duration;
+    use view::View;
+
+    // A red miner next to a hidden blue piece, which it might or might not
+    // beat.
+    let mut board = Board::new();
+    for &(c, piece, colour) in &[
+        ("a9", Piece::Flag, Colour::Red),
+        ("j4", Piece::Miner, Colour::Red),
+        ("j0", Piece::Flag, Colour::Blue),
+        ("j3", Piece::Sergeant, Colour::Blue),
+    ] {
+        board.set_tile(Coord::from(c).unwrap(), Tile::Piece(piece, colour));
+    }
+    let view = View::new(&board, Colour::Red);
+    let mut belief = Belief::new(Colour::Red, Rules::default());
+    belief.update(&view);
+    let position = Position::new(&view, &belief);
+    let hidden = Coord::from("j3").unwrap();
+    assert_eq!(position.hidden(hidden).unwrap().to_vec(), belief.odds(hidden));
+
+    // Striking it could turn out as many ways as there are ranks it might be.
+    let battles = position.play(mv("j4", "j3"));
+    assert_eq!(battles.len(), belief.odds(hidden).len());
+    assert!((battles.iter().map(|&(_, chance)| chance).sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(battles.iter().all(|(p, _)| p.hidden(hidden).is_none()));
+    assert!(battles.iter().all(|(p, _)| p.board().to_move() == Colour::Blue));
+    let quiet = position.play(mv("j4", "i4"));
+    assert_eq!(quiet.len(), 1);
+    assert_eq!(quiet[0].1, 1.0);
+    assert!(quiet[0].0.hidden(hidden).is_some());
+
+    // The red marshall takes the blue general.
+    let board = general_strike_board();
+    let view = View::new(&board, Colour::Red);
+    for &depth in &[1, 2] {
+        let mut player = Expectimax::new(Budget::Depth(depth), 1).unwrap();
+        assert_eq!(player.choose_move(&view).unwrap(), mv("e7", "e6"));
+        let mut player = Expectimax::with_evaluation(Budget::Depth(depth), 1, Box::new(Material)).unwrap();
+        assert_eq!(player.choose_move(&view).unwrap(), mv("e7", "e6"));
+    }
+    let m = Expectimax::new(Budget::Time(Duration::from_millis(50)), 1).unwrap().choose_move(&view).unwrap();
+    assert!(view.moves().contains(&m));
+    // It searches to a depth or for a time, not in rounds.
+    assert_eq!(
+        Expectimax::new(Budget::Iterations(2000), 1).err(),
+        Some(BotError::Budget("expectimax", Budget::Iterations(2000)))
+    );
+    let player = Expectimax::new(Expectimax::BUDGET, 1).unwrap();
+    assert!(format!("{:?}", player).starts_with("Expectimax { budget: Depth(3)"));
+    assert_eq!(format!("{:?}", Weighted::default()), "Weighted { weights: [1.0, 0.5, 1.0, 0.2], .. }");
+
+    // Other evaluations can be plugged in, such as one that only wants to
+    // keep the marshall back.
+    struct Back;
+    impl Evaluation for Back {
+        fn evaluate(&self, position: &Position) -> f64 {
+            let board = position.board();
+            board
+                .geometry()
+                .coords()
+                .find(|&c| board.tile_at(c) == Tile::Piece(Piece::Marshall, Colour::Red))
+                .map_or(0.0, |c| c.y as f64)
+        }
+    }
+    let mut player = Expectimax::with_evaluation(Budget::Depth(1), 1, Box::new(Back)).unwrap();
+    assert_eq!(player.choose_move(&view).unwrap(), mv("e7", "e8"));
+
+    // It beats a player moving at random.
+    let wins = wins_against_random(|seed| Expectimax::new(Budget::Depth(2), seed).unwrap(), 4);
+    assert!(wins >= 3, "won {} of 4", wins);
+}
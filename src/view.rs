@@ -0,0 +1,98 @@
+//! What each player can see of the game.
+//!
+//! Players only know the ranks of their own pieces, and of enemy pieces that
+//! have been revealed in battle. They can also see which enemy pieces have
+//! moved, and so can't be bombs or flags.
+
+use board::{Board, Colour, Coord, Move, Piece, Tile};
+use rules::Rules;
+
+/// A tile as seen by one player.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Seen {
+    Terrain,
+    Empty,
+    /// One of the viewer's own pieces, and whether the opponent has seen its
+    /// rank or seen it move.
+    Own {piece: Piece, revealed: bool, moved: bool},
+    /// An enemy piece, with its rank if it has been revealed in battle.
+    Enemy {rank: Option<Piece>, moved: bool},
+}
+
+/// One player's view of the game, with everything they can't know hidden.
+///
+/// This is all that bots and remote players are given, so that they can't
+/// cheat.
+#[derive(Clone, Debug)]
+pub struct View {
+    /// The colour of the viewer.
+    pub colour:         Colour,
+    /// The colour whose turn it is.
+    pub to_move:        Colour,
+    /// The rules of the game, including the army each player started with
+    /// and the shape of the board.
+    pub rules:          Rules,
+    /// The viewer's pieces that have been captured.
+    pub own_captured:   Vec<Piece>,
+    /// The opponent's pieces that have been captured.
+    pub enemy_captured: Vec<Piece>,
+    tiles:              Vec<Vec<Seen>>,
+    moves:              Vec<Move>,
+}
+
+impl View {
+    /// Builds the view of the game for the given colour.
+    pub fn new(board: &Board, colour: Colour) -> Self {
+        let mut own_captured = vec![];
+        let mut enemy_captured = vec![];
+        for outcome in board.history() {
+            for (_, piece, col) in outcome.casualties() {
+                if col == colour {
+                    own_captured.push(piece);
+                } else {
+                    enemy_captured.push(piece);
+                }
+            }
+        }
+
+        let geometry = board.geometry();
+        let mut tiles =
+            vec![vec![Seen::Empty; geometry.width as usize]; geometry.height as usize];
+        for (y, row) in tiles.iter_mut().enumerate() {
+            for (x, seen) in row.iter_mut().enumerate() {
+                let c = Coord {x: x as u16, y: y as u16};
+                let (revealed, moved) = match board.info_at(c) {
+                    Some(i) => (i.revealed, i.moved),
+                    None => (false, false),
+                };
+                *seen = match board.tile_at(c) {
+                    Tile::Terrain => Seen::Terrain,
+                    Tile::Empty => Seen::Empty,
+                    Tile::Piece(piece, col) if col == colour =>
+                        Seen::Own {piece, revealed, moved},
+                    Tile::Piece(piece, _) => Seen::Enemy {
+                        rank: if revealed { Some(piece) } else { None },
+                        moved,
+                    },
+                };
+            }
+        }
+
+        View {
+            colour,
+            to_move: board.to_move(),
+            rules: board.rules(),
+            own_captured,
+            enemy_captured,
+            tiles,
+            moves: board.find_all_moves(colour),
+        }
+    }
+
+    pub fn tile_at(&self, c: Coord) -> Seen {
+        self.tiles[c.y as usize][c.x as usize]
+    }
+
+    /// All legal moves available to the viewer's pieces.
+    pub fn moves(&self) -> &[Move] {&self.moves}
+}